@@ -0,0 +1,38 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{fs, io, path::Path};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Size of the nonces sent in `AuthChallenge`.
+pub const NONCE_LEN: usize = 32;
+
+/// Reads a shared secret, ignoring the whitespace around it so that a trailing newline does not
+/// count.
+pub fn read_secret(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let secret = fs::read(path)?;
+    let secret = secret.trim_ascii();
+    if secret.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the secret file is empty",
+        ));
+    }
+    Ok(secret.to_vec())
+}
+
+/// Answer to an `AuthChallenge`, the HMAC-SHA256 of `nonce` keyed with the shared secret.
+pub fn sign_challenge(secret: &[u8], nonce: &[u8]) -> Vec<u8> {
+    mac(secret, nonce).finalize().into_bytes().to_vec()
+}
+
+/// Checks an answer to an `AuthChallenge` in constant time.
+pub fn verify_challenge(secret: &[u8], nonce: &[u8], signature: &[u8]) -> bool {
+    mac(secret, nonce).verify_slice(signature).is_ok()
+}
+
+fn mac(secret: &[u8], nonce: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(nonce);
+    mac
+}
@@ -1,11 +1,81 @@
+use crate::{ErrorKind, Event, Hello, LogLine, SignalResult};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ClientCommand {
-    SuccessfulConnection,
+    /// Sent before `Hello` when the server requires authentication, the client has to answer
+    /// with `Authenticate`
+    AuthChallenge {
+        nonce: Vec<u8>,
+    },
+
+    /// The connection will be closed after sending this command
+    AuthenticationFailed,
+
+    /// Greeting of the server, see `Hello` for the compatibility policy
+    Hello(Hello),
 
     /// The connection will be closed after sending this command
     FailedToParseFrame,
 
     TaskList(Vec<String>),
+
+    LogsReopened,
+
+    /// Response to `Tail`, oldest line first
+    LogLines(Vec<LogLine>),
+
+    /// A line of output sent while following logs
+    LogLine(LogLine),
+
+    LogStreamEnded,
+
+    /// An event sent to a subscribed client
+    Event(Event),
+
+    EventStreamEnded,
+
+    /// Response to `Start`
+    Started {
+        target: String,
+    },
+
+    /// Response to `Stop`, once every process of the target has exited
+    Stopped {
+        target: String,
+    },
+
+    /// Response to `Signal`, one result per running process of the target
+    SignalSent(Vec<SignalResult>),
+
+    /// Response to a command that failed, the connection stays open. `target` is the one of the
+    /// command, if it has one.
+    Error {
+        kind: ErrorKind,
+        message: String,
+        target: Option<String>,
+    },
+
+    /// Response to `Wait`, once every process of the target is `Running`
+    Ready {
+        target: String,
+    },
+}
+
+impl ClientCommand {
+    pub fn error(kind: ErrorKind, message: impl Into<String>, target: Option<String>) -> Self {
+        Self::Error {
+            kind,
+            message: message.into(),
+            target,
+        }
+    }
+
+    pub fn no_such_program(target: String) -> Self {
+        Self::error(
+            ErrorKind::NoSuchProgram,
+            format!("No such program: `{target}`"),
+            Some(target),
+        )
+    }
 }
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// Why a command failed, sent in `ClientCommand::Error` along with a message for humans.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The target does not match any program or instance
+    NoSuchProgram,
+    /// Every process of the target is already running
+    AlreadyRunning,
+    /// A process of the target did not reach `Running`
+    StartFailed,
+    /// The signal name is not a known signal
+    InvalidSignal,
+    /// The role of the client is below the one the command requires
+    PermissionDenied,
+    /// The server does not handle the command, see `Feature`
+    UnsupportedCommand,
+    /// The id of the request is the one of a request still in flight on the connection
+    DuplicateRequest,
+    /// The server failed to handle the command
+    Internal,
+    /// A process of the target is not running, or did not reach `Running` in time
+    NotReady,
+    /// A kind added by a newer server
+    #[serde(other)]
+    Unknown,
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+/// State of a process, as reported by its supervising routine.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ProcessStatus {
+    Starting,
+    Running,
+    /// The process exited before it was ready, `signal` is the one that killed it if any
+    ErrorDuringStartup {
+        exit_code: Option<i32>,
+        signal: Option<i32>,
+    },
+    FailedToSpawn {
+        error: String,
+    },
+    /// The process exited after it was ready, `signal` is the one that killed it if any
+    Exited {
+        exit_code: Option<i32>,
+        signal: Option<i32>,
+    },
+    /// Reported by an `unhealthy` trigger, the process keeps running
+    Unhealthy {
+        pattern: String,
+        line: String,
+    },
+}
+
+/// Something that happened in the daemon, pushed to the clients that subscribed to it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Event {
+    /// A process changed state
+    Status {
+        program: String,
+        instance: u32,
+        status: ProcessStatus,
+    },
+    /// A line of output of a process matched a trigger with the `event` action
+    Trigger {
+        program: String,
+        instance: u32,
+        pattern: String,
+        line: String,
+    },
+    /// A client connected to the daemon
+    ClientConnected { client_id: i32 },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EventKind {
+    Status,
+    Trigger,
+    ClientConnect,
+}
+
+/// Selects the events a client subscribes to. An empty list does not filter anything out.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EventFilter {
+    pub kinds: Vec<EventKind>,
+    /// Only applies to the events about a process
+    pub programs: Vec<String>,
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::Status { .. } => EventKind::Status,
+            Event::Trigger { .. } => EventKind::Trigger,
+            Event::ClientConnected { .. } => EventKind::ClientConnect,
+        }
+    }
+
+    /// Name of the program the event is about, if any.
+    pub fn program(&self) -> Option<&str> {
+        match self {
+            Event::Status { program, .. } | Event::Trigger { program, .. } => Some(program),
+            Event::ClientConnected { .. } => None,
+        }
+    }
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &Event) -> bool {
+        let kind_matches = self.kinds.is_empty() || self.kinds.contains(&event.kind());
+        let program_matches = match event.program() {
+            Some(program) => {
+                self.programs.is_empty() || self.programs.iter().any(|name| name == program)
+            }
+            None => true,
+        };
+        kind_matches && program_matches
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn status(program: &str) -> Event {
+        Event::Status {
+            program: program.to_string(),
+            instance: 0,
+            status: ProcessStatus::Running,
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let trigger = Event::Trigger {
+            program: "web".to_string(),
+            instance: 1,
+            pattern: "pool".to_string(),
+            line: "pool exhausted".to_string(),
+        };
+        let client = Event::ClientConnected { client_id: 3 };
+
+        let all = EventFilter::default();
+        assert!(all.matches(&status("web")));
+        assert!(all.matches(&trigger));
+        assert!(all.matches(&client));
+
+        let statuses = EventFilter {
+            kinds: vec![EventKind::Status],
+            programs: vec![],
+        };
+        assert!(statuses.matches(&status("web")));
+        assert!(!statuses.matches(&trigger));
+        assert!(!statuses.matches(&client));
+
+        let web = EventFilter {
+            kinds: vec![],
+            programs: vec!["web".to_string()],
+        };
+        assert!(web.matches(&status("web")));
+        assert!(web.matches(&trigger));
+        assert!(!web.matches(&status("worker")));
+        // Events about no program are not filtered out by the programs
+        assert!(web.matches(&client));
+
+        let web_triggers = EventFilter {
+            kinds: vec![EventKind::Trigger],
+            programs: vec!["web".to_string()],
+        };
+        assert!(web_triggers.matches(&trigger));
+        assert!(!web_triggers.matches(&status("web")));
+    }
+}
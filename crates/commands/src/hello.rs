@@ -0,0 +1,80 @@
+//! Compatibility policy between the clients and the server:
+//! - `PROTOCOL_VERSION` only changes when an existing frame changes in a breaking way, clients
+//!   refuse to talk to a server greeting them with another version.
+//! - New commands come with a `Feature`, advertised by the servers handling them. Clients only
+//!   send a command when the server advertised its feature, and ignore the features they do not
+//!   know.
+//! - What comes before `Hello` cannot be negotiated and must stay the same for a version to be
+//!   told apart: the line naming the codec, how the frames are delimited, the `Request` and
+//!   `Response` envelopes and the authentication challenge.
+//!
+//! Version 2 broke the opening of the connection of version 1 all at once, by adding the codec
+//! line, the length prefix of the frames and their request ids, and typed errors. A version 1
+//! client gets no greeting it can read from a version 2 server.
+
+use crate::ServerCommand;
+use serde::{Deserialize, Serialize};
+
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// A group of optional commands.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Feature {
+    /// `Start` and `Stop`
+    StartStop,
+    Restart,
+    ReopenLogs,
+    Tail,
+    /// `FollowLogs` and `UnfollowLogs`
+    FollowLogs,
+    /// `Subscribe` and `Unsubscribe`
+    Events,
+    Signal,
+    Wait,
+    /// A feature added by a newer peer
+    #[serde(other)]
+    Unknown,
+}
+
+/// Greeting of the server, once the client is authenticated if it has to.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Hello {
+    pub protocol_version: u32,
+    pub features: Vec<Feature>,
+    /// Version of the daemon, for display only
+    pub server_version: String,
+}
+
+impl Hello {
+    pub fn is_compatible(&self) -> bool {
+        self.protocol_version == PROTOCOL_VERSION
+    }
+
+    pub fn supports(&self, command: &ServerCommand) -> bool {
+        command
+            .required_feature()
+            .is_none_or(|feature| self.features.contains(&feature))
+    }
+}
+
+impl ServerCommand {
+    /// Feature the server has to advertise for the command to be sent, `None` for the commands
+    /// every server handles.
+    pub fn required_feature(&self) -> Option<Feature> {
+        match self {
+            ServerCommand::Authenticate { .. } | ServerCommand::ListTasks => None,
+            ServerCommand::Start { .. } | ServerCommand::Stop { .. } => Some(Feature::StartStop),
+            ServerCommand::Restart { .. } => Some(Feature::Restart),
+            ServerCommand::ReopenLogs => Some(Feature::ReopenLogs),
+            ServerCommand::Tail { .. } => Some(Feature::Tail),
+            ServerCommand::FollowLogs { .. } | ServerCommand::UnfollowLogs { .. } => {
+                Some(Feature::FollowLogs)
+            }
+            ServerCommand::Subscribe { .. } | ServerCommand::Unsubscribe { .. } => {
+                Some(Feature::Events)
+            }
+            ServerCommand::Signal { .. } => Some(Feature::Signal),
+            ServerCommand::Wait { .. } => Some(Feature::Wait),
+        }
+    }
+}
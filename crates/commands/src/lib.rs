@@ -3,3 +3,26 @@ pub use server_command::ServerCommand;
 
 mod client_command;
 pub use client_command::ClientCommand;
+
+mod log_line;
+pub use log_line::{LogLine, LogStream};
+
+mod signal_result;
+pub use signal_result::SignalResult;
+
+mod event;
+pub use event::{Event, EventFilter, EventKind, ProcessStatus};
+
+mod role;
+pub use role::Role;
+
+pub mod auth;
+
+mod hello;
+pub use hello::{Feature, Hello, PROTOCOL_VERSION};
+
+mod error_kind;
+pub use error_kind::ErrorKind;
+
+mod request;
+pub use request::{IncomingRequest, Request, RequestId, Response};
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+    /// Stdout and stderr of a program with `redirect_stderr`, which can not be told apart
+    Merged,
+}
+
+/// A line of output captured from a process.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LogLine {
+    pub program: String,
+    pub instance: u32,
+    pub stream: LogStream,
+    pub message: String,
+}
@@ -0,0 +1,61 @@
+use crate::{ClientCommand, ServerCommand};
+use serde::{Deserialize, Deserializer, Serialize, de::IgnoredAny};
+
+/// Chosen by the client, it should not be reused while the request is in flight.
+pub type RequestId = u32;
+
+/// Frame sent by the clients. The server handles the requests of a connection concurrently.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Request {
+    pub id: RequestId,
+    pub command: ServerCommand,
+}
+
+/// A `Request` as read by the server. `command` is `None` when the server does not know it, for
+/// a client newer than the server, so that it can still be answered.
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct IncomingRequest {
+    pub id: RequestId,
+    #[serde(deserialize_with = "deserialize_known")]
+    pub command: Option<ServerCommand>,
+}
+
+fn deserialize_known<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MaybeKnown<T> {
+        Known(T),
+        Unknown(IgnoredAny),
+    }
+
+    Ok(match MaybeKnown::deserialize(deserializer)? {
+        MaybeKnown::Known(value) => Some(value),
+        MaybeKnown::Unknown(_) => None,
+    })
+}
+
+/// Frame sent by the server, in any order. `id` is the one of the request being answered, or
+/// `None` for the frames the server sends on its own: `AuthChallenge`, `AuthenticationFailed`,
+/// the greeting and `FailedToParseFrame`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Response {
+    pub id: Option<RequestId>,
+    pub command: ClientCommand,
+}
+
+impl Response {
+    pub fn new(id: RequestId, command: ClientCommand) -> Self {
+        Self {
+            id: Some(id),
+            command,
+        }
+    }
+
+    pub fn unsolicited(command: ClientCommand) -> Self {
+        Self { id: None, command }
+    }
+}
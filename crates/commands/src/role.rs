@@ -0,0 +1,35 @@
+use crate::ServerCommand;
+use serde::{Deserialize, Serialize};
+
+/// What a client is allowed to do, each role allowing everything the previous ones do.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Inspect the processes and their output
+    ReadOnly,
+    /// Control the processes
+    Operator,
+    /// Manage the daemon itself
+    Admin,
+}
+
+impl ServerCommand {
+    /// Least role allowed to send the command.
+    pub fn required_role(&self) -> Role {
+        match self {
+            ServerCommand::Authenticate { .. }
+            | ServerCommand::ListTasks
+            | ServerCommand::Wait { .. }
+            | ServerCommand::Tail { .. }
+            | ServerCommand::FollowLogs { .. }
+            | ServerCommand::UnfollowLogs { .. }
+            | ServerCommand::Subscribe { .. }
+            | ServerCommand::Unsubscribe { .. } => Role::ReadOnly,
+            ServerCommand::Start { .. }
+            | ServerCommand::Stop { .. }
+            | ServerCommand::Restart { .. }
+            | ServerCommand::Signal { .. }
+            | ServerCommand::ReopenLogs => Role::Operator,
+        }
+    }
+}
@@ -1,9 +1,100 @@
+use crate::{EventFilter, LogStream, RequestId};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ServerCommand {
+    /// Answer to `AuthChallenge`, see `auth::sign_challenge`
+    Authenticate {
+        signature: Vec<u8>,
+    },
+
     ListTasks,
-    Stop { target: String },
-    Restart { target: String },
-    Start { target: String },
+    /// Stop every process of `target`. `timeout` overrides the `stoptime` of the program and
+    /// `kill` sends SIGKILL right away instead of its `stopsignal`.
+    Stop {
+        target: String,
+        timeout: Option<u32>,
+        kill: bool,
+    },
+    Restart {
+        target: String,
+    },
+    /// Start the processes of `target` that are not running. With `wait`, the response is only
+    /// sent once they are all `Running`, or once one of them failed to start.
+    Start {
+        target: String,
+        wait: bool,
+    },
+
+    /// Reopen the output files of every process, for use after an external log rotation
+    ReopenLogs,
+
+    /// Get the last `lines` captured from the output of `target`, either `program` or
+    /// `program:instance`. When `stream` is `None`, both stdout and stderr are returned.
+    Tail {
+        target: String,
+        lines: usize,
+        stream: Option<LogStream>,
+    },
+
+    /// Stream `LogLine` frames with the output of `targets`, from the given `streams` or from
+    /// every stream if empty. The stream ends with `LogStreamEnded` once every followed process
+    /// is gone or once the client sends `UnfollowLogs`, and other requests can be sent meanwhile.
+    FollowLogs {
+        targets: Vec<String>,
+        streams: Vec<LogStream>,
+    },
+
+    /// End the `FollowLogs` stream of request `id`. It is answered with `LogStreamEnded` even if
+    /// the stream had already ended.
+    UnfollowLogs {
+        id: RequestId,
+    },
+
+    /// Stream `Event` frames with the events selected by `filter`, until the client sends
+    /// `Unsubscribe`. The stream ends with `EventStreamEnded`.
+    Subscribe {
+        filter: EventFilter,
+    },
+
+    /// End the `Subscribe` stream of request `id`, answered like `UnfollowLogs`
+    Unsubscribe {
+        id: RequestId,
+    },
+
+    /// Send `signal`, a name like `HUP` or `SIGHUP`, to every running process of `target`, or to
+    /// their whole process group with `process_group`
+    Signal {
+        target: String,
+        signal: String,
+        process_group: bool,
+    },
+
+    /// Wait for every process of `target` to be `Running`, for at most `timeout` seconds, or for
+    /// as long as starting them would take when `None`
+    Wait {
+        target: String,
+        timeout: Option<u32>,
+    },
+}
+
+impl ServerCommand {
+    /// The program or instance the command is about, if it has a single one.
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            ServerCommand::Stop { target, .. }
+            | ServerCommand::Restart { target }
+            | ServerCommand::Start { target, .. }
+            | ServerCommand::Wait { target, .. }
+            | ServerCommand::Tail { target, .. }
+            | ServerCommand::Signal { target, .. } => Some(target),
+            ServerCommand::Authenticate { .. }
+            | ServerCommand::ListTasks
+            | ServerCommand::ReopenLogs
+            | ServerCommand::FollowLogs { .. }
+            | ServerCommand::UnfollowLogs { .. }
+            | ServerCommand::Subscribe { .. }
+            | ServerCommand::Unsubscribe { .. } => None,
+        }
+    }
 }
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// Outcome of sending a signal to one process.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SignalResult {
+    pub program: String,
+    pub instance: u32,
+    /// Why the signal could not be delivered, if it could not
+    pub error: Option<String>,
+}
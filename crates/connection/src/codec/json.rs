@@ -0,0 +1,145 @@
+use super::Codec;
+use crate::{Error, Result};
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Each frame is sent as a line of JSON. Blank lines are skipped, so that the frames can be typed
+/// by hand.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Json {
+    /// Bytes of the incomplete line at the start of the buffer already known to hold no newline
+    scanned: usize,
+}
+
+impl Codec for Json {
+    const NAME: &'static str = "json";
+
+    fn encode<T: Serialize>(
+        &self,
+        frame: &T,
+        buffer: &mut Vec<u8>,
+        max_frame_size: usize,
+    ) -> Result<()> {
+        let encoded_frame =
+            serde_json::to_vec(frame).map_err(|error| Error::FailedToEncodeFrame(error.into()))?;
+        if encoded_frame.len() > max_frame_size {
+            return Err(Error::FrameTooLarge {
+                size: encoded_frame.len(),
+                max: max_frame_size,
+            });
+        }
+
+        buffer.extend(encoded_frame);
+        buffer.push(b'\n');
+        Ok(())
+    }
+
+    fn decode<T: DeserializeOwned>(
+        &mut self,
+        buffer: &[u8],
+        max_frame_size: usize,
+    ) -> Result<(Option<T>, usize)> {
+        // Dropped right away, so that blank lines never pile up in the buffer
+        let start = buffer
+            .iter()
+            .position(|byte| !byte.is_ascii_whitespace())
+            .unwrap_or(buffer.len());
+
+        let line = &buffer[start..];
+        let scanned = std::mem::take(&mut self.scanned).min(line.len());
+        let Some(size) = line[scanned..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map(|position| scanned + position)
+        else {
+            if line.len() > max_frame_size {
+                return Err(Error::FrameTooLarge {
+                    size: line.len(),
+                    max: max_frame_size,
+                });
+            }
+            self.scanned = line.len();
+            return Ok((None, start));
+        };
+        if size > max_frame_size {
+            return Err(Error::FrameTooLarge {
+                size,
+                max: max_frame_size,
+            });
+        }
+
+        let frame = serde_json::from_slice(&line[..size])
+            .map_err(|error| Error::FailedToDecodeFrame(error.into()))?;
+        Ok((Some(frame), start + size + 1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAX: usize = 64;
+
+    fn decode(buffer: &[u8]) -> Result<(Option<Vec<u32>>, usize)> {
+        Json::default().decode(buffer, MAX)
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut buffer = Vec::new();
+        Json::default()
+            .encode(&vec![1, 2], &mut buffer, MAX)
+            .unwrap();
+        Json::default().encode(&vec![3], &mut buffer, MAX).unwrap();
+        assert_eq!(buffer, b"[1,2]\n[3]\n");
+
+        assert!(matches!(
+            Json::default().encode(&vec![0; MAX], &mut buffer, MAX),
+            Err(Error::FrameTooLarge { max: MAX, .. })
+        ));
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(b"[1, 2]\n[3]").unwrap(), (Some(vec![1, 2]), 7));
+        assert_eq!(decode(b"[1, 2").unwrap(), (None, 0));
+        assert_eq!(decode(b"\r\n \n[1]\r\n").unwrap(), (Some(vec![1]), 9));
+        assert!(matches!(
+            decode(b"[1, \"2\"]\n"),
+            Err(Error::FailedToDecodeFrame(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_blank_lines_dropped() {
+        assert_eq!(decode(b"\n\n").unwrap(), (None, 2));
+        assert_eq!(decode(b" \r\n[1, ").unwrap(), (None, 3));
+
+        // Blank lines do not count toward the size of a frame
+        let mut buffer = vec![b'\n'; MAX * 2];
+        buffer.extend(b"[1]\n");
+        assert_eq!(decode(&buffer).unwrap(), (Some(vec![1]), MAX * 2 + 4));
+    }
+
+    #[test]
+    fn test_decode_partial_line() {
+        let mut codec = Json::default();
+        let mut buffer = b"\n[1,".to_vec();
+        let (frame, dropped) = codec.decode::<Vec<u32>>(&buffer, MAX).unwrap();
+        assert_eq!((frame, dropped), (None, 1));
+        buffer.drain(..dropped);
+
+        buffer.extend(b" 2]\n[3]\n");
+        assert_eq!(codec.decode(&buffer, MAX).unwrap(), (Some(vec![1, 2]), 7));
+        buffer.drain(..7);
+        assert_eq!(codec.decode(&buffer, MAX).unwrap(), (Some(vec![3]), 4));
+    }
+
+    #[test]
+    fn test_decode_too_large() {
+        let line = vec![b'1'; MAX + 1];
+        assert!(matches!(
+            decode(&line),
+            Err(Error::FrameTooLarge { max: MAX, .. })
+        ));
+    }
+}
@@ -0,0 +1,63 @@
+use super::Codec;
+use crate::{Error, Result};
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Size of the big endian `u32` preceding each frame with the size of its encoding
+const LENGTH_PREFIX_SIZE: usize = 4;
+
+/// Each frame is sent as its size followed by its MessagePack encoding.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MessagePack;
+
+impl Codec for MessagePack {
+    const NAME: &'static str = "msgpack";
+
+    fn encode<T: Serialize>(
+        &self,
+        frame: &T,
+        buffer: &mut Vec<u8>,
+        max_frame_size: usize,
+    ) -> Result<()> {
+        let encoded_frame =
+            rmp_serde::to_vec(frame).map_err(|error| Error::FailedToEncodeFrame(error.into()))?;
+        let size = encoded_frame.len();
+        let prefix = match u32::try_from(size) {
+            Ok(prefix) if size <= max_frame_size => prefix,
+            _ => {
+                return Err(Error::FrameTooLarge {
+                    size,
+                    max: max_frame_size,
+                });
+            }
+        };
+
+        buffer.extend_from_slice(&prefix.to_be_bytes());
+        buffer.extend(encoded_frame);
+        Ok(())
+    }
+
+    fn decode<T: DeserializeOwned>(
+        &mut self,
+        buffer: &[u8],
+        max_frame_size: usize,
+    ) -> Result<(Option<T>, usize)> {
+        let Some(prefix) = buffer.first_chunk::<LENGTH_PREFIX_SIZE>() else {
+            return Ok((None, 0));
+        };
+        let size = u32::from_be_bytes(*prefix) as usize;
+        if size > max_frame_size {
+            return Err(Error::FrameTooLarge {
+                size,
+                max: max_frame_size,
+            });
+        }
+
+        let frame_end = LENGTH_PREFIX_SIZE + size;
+        let Some(encoded_frame) = buffer.get(LENGTH_PREFIX_SIZE..frame_end) else {
+            return Ok((None, 0));
+        };
+        let frame = rmp_serde::from_slice(encoded_frame)
+            .map_err(|error| Error::FailedToDecodeFrame(error.into()))?;
+        Ok((Some(frame), frame_end))
+    }
+}
@@ -0,0 +1,85 @@
+//! How frames are laid out on the stream. The codec of a connection is picked by the client,
+//! which starts by sending the `NAME` of one followed by a newline.
+
+mod json;
+pub use json::Json;
+
+mod message_pack;
+pub use message_pack::MessagePack;
+
+use crate::Result;
+use serde::{Serialize, de::DeserializeOwned};
+
+pub trait Codec {
+    /// Sent by the client to pick the codec
+    const NAME: &'static str;
+
+    /// Appends `frame` to `buffer`, with whatever tells where it ends.
+    fn encode<T: Serialize>(
+        &self,
+        frame: &T,
+        buffer: &mut Vec<u8>,
+        max_frame_size: usize,
+    ) -> Result<()>;
+
+    /// Decodes the first frame of `buffer` if it is complete, and returns it along with the number
+    /// of bytes to drop from the start of `buffer`. The caller drops them before decoding again
+    /// from the same stream, since the codec may remember what it already went through.
+    fn decode<T: DeserializeOwned>(
+        &mut self,
+        buffer: &[u8],
+        max_frame_size: usize,
+    ) -> Result<(Option<T>, usize)>;
+}
+
+/// One of the codecs, for connections whose codec is only known at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnyCodec {
+    MessagePack(MessagePack),
+    Json(Json),
+}
+
+impl AnyCodec {
+    /// Finds the codec named `name`, see `Codec::NAME`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            MessagePack::NAME => Some(Self::MessagePack(MessagePack)),
+            Json::NAME => Some(Self::Json(Json::default())),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MessagePack(_) => MessagePack::NAME,
+            Self::Json(_) => Json::NAME,
+        }
+    }
+}
+
+impl Codec for AnyCodec {
+    const NAME: &'static str = "any";
+
+    fn encode<T: Serialize>(
+        &self,
+        frame: &T,
+        buffer: &mut Vec<u8>,
+        max_frame_size: usize,
+    ) -> Result<()> {
+        match self {
+            Self::MessagePack(codec) => codec.encode(frame, buffer, max_frame_size),
+            Self::Json(codec) => codec.encode(frame, buffer, max_frame_size),
+        }
+    }
+
+    fn decode<T: DeserializeOwned>(
+        &mut self,
+        buffer: &[u8],
+        max_frame_size: usize,
+    ) -> Result<(Option<T>, usize)> {
+        match self {
+            Self::MessagePack(codec) => codec.decode(buffer, max_frame_size),
+            Self::Json(codec) => codec.decode(buffer, max_frame_size),
+        }
+    }
+}
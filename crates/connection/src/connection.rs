@@ -1,12 +1,13 @@
-use std::{io::Cursor, marker::PhantomData};
-
 use serde::{Serialize, de::DeserializeOwned};
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio::io::{AsyncRead, AsyncWrite, ReadHalf, WriteHalf};
+
+use crate::{Codec, FrameReader, FrameWriter, MessagePack, Result};
 
-use crate::{Error, FrameDecodeError, Result};
+/// Frames above 8 MiB are refused unless `Connection::with_max_frame_size` says otherwise
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 8 << 20;
 
 /// Struct used to send and recieve any type that implements serdes `Serialize` and
-/// `DeserializeOwned`.
+/// `DeserializeOwned`, laid out on the stream by `Codec`.
 ///
 /// Example:
 /// ```
@@ -25,85 +26,72 @@ use crate::{Error, FrameDecodeError, Result};
 /// });
 /// ```
 #[derive(Debug)]
-pub struct Connection<Socket, InputFrame, OutputFrame> {
-    stream: BufWriter<Socket>,
-    buffer: Vec<u8>,
-
-    _input_frame_type: PhantomData<InputFrame>,
-    _output_frame_type: PhantomData<OutputFrame>,
+pub struct Connection<Socket, InputFrame, OutputFrame, C = MessagePack> {
+    reader: ConnectionReader<Socket, InputFrame, C>,
+    writer: ConnectionWriter<Socket, OutputFrame, C>,
 }
 
-impl<Socket, InputFrame, OutputFrame> Connection<Socket, InputFrame, OutputFrame>
+/// Read half of a split `Connection`
+pub type ConnectionReader<Socket, InputFrame, C = MessagePack> =
+    FrameReader<ReadHalf<Socket>, InputFrame, C>;
+
+/// Write half of a split `Connection`
+pub type ConnectionWriter<Socket, OutputFrame, C = MessagePack> =
+    FrameWriter<WriteHalf<Socket>, OutputFrame, C>;
+
+impl<Socket, InputFrame, OutputFrame, C> Connection<Socket, InputFrame, OutputFrame, C>
 where
     Socket: AsyncWrite + AsyncRead + Unpin,
     InputFrame: DeserializeOwned,
     OutputFrame: Serialize,
+    C: Codec + Clone,
 {
-    pub fn new(socket: Socket, buffer_capacity: usize) -> Self {
-        Self {
-            stream: BufWriter::new(socket),
-            buffer: Vec::with_capacity(buffer_capacity),
+    pub fn new(socket: Socket, buffer_capacity: usize) -> Self
+    where
+        C: Default,
+    {
+        Self::with_codec(socket, buffer_capacity, C::default())
+    }
 
-            _input_frame_type: std::marker::PhantomData,
-            _output_frame_type: std::marker::PhantomData,
+    pub fn with_codec(socket: Socket, buffer_capacity: usize, codec: C) -> Self {
+        let (reader, writer) = tokio::io::split(socket);
+        Self {
+            reader: FrameReader::new(reader, buffer_capacity, codec.clone()),
+            writer: FrameWriter::new(writer, codec),
         }
     }
 
-    pub async fn read_frame(&mut self) -> Result<Option<InputFrame>> {
-        loop {
-            if let Some(frame) = self.parse_frame()? {
-                return Ok(Some(frame));
-            }
-
-            // On success, the number of bytes is returned. `0` indicates "end of stream".
-            if 0 == self
-                .stream
-                .read_buf(&mut self.buffer)
-                .await
-                .map_err(Error::FailedToReadFromStream)?
-            {
-                if self.buffer.is_empty() {
-                    return Ok(None);
-                } else {
-                    return Err(Error::ConnectionReset);
-                }
-            }
+    /// Sets the size above which the frames are refused, in both directions, not counting what
+    /// delimits them.
+    pub fn with_max_frame_size(self, max_frame_size: usize) -> Self {
+        Self {
+            reader: self.reader.with_max_frame_size(max_frame_size),
+            writer: self.writer.with_max_frame_size(max_frame_size),
         }
     }
 
-    fn parse_frame(&mut self) -> Result<Option<InputFrame>> {
-        let mut buf = Cursor::new(&self.buffer[..]);
-
-        match rmp_serde::from_read::<_, InputFrame>(&mut buf) {
-            Ok(frame) => {
-                let nb_of_bytes_read = buf.position() as usize;
-                self.buffer.drain(0..nb_of_bytes_read);
-
-                Ok(Some(frame))
-            }
-
-            // The buffer does not contain a complete frame. This is not an error we just need to
-            // keep reading
-            Err(FrameDecodeError::InvalidDataRead(_)) => Ok(None),
-            Err(FrameDecodeError::InvalidMarkerRead(_)) => Ok(None),
-
-            Err(err) => Err(Error::FailedToDecodeFrame(err)),
-        }
+    pub async fn read_frame(&mut self) -> Result<Option<InputFrame>> {
+        self.reader.read_frame().await
     }
 
     pub async fn write_frame(&mut self, frame: &OutputFrame) -> Result<()> {
-        let encoded_frame = rmp_serde::to_vec(frame).map_err(Error::FailedToEncodeFrame)?;
+        self.writer.write_frame(frame).await
+    }
 
-        self.stream
-            .write_all(&encoded_frame)
-            .await
-            .map_err(Error::FailedToWriteToStream)?;
+    /// Closes the write half of the stream, letting the other end read the end of stream. A TLS
+    /// stream also tells its peer the connection was not truncated.
+    pub async fn shutdown(&mut self) -> Result<()> {
+        self.writer.shutdown().await
+    }
 
-        self.stream
-            .flush()
-            .await
-            .map_err(Error::FailedToWriteToStream)?;
-        Ok(())
+    /// Splits the connection so that frames can be read and written from separate tasks.
+    pub fn into_split(
+        self,
+    ) -> (
+        ConnectionReader<Socket, InputFrame, C>,
+        ConnectionWriter<Socket, OutputFrame, C>,
+    ) {
+        (self.reader, self.writer)
     }
 }
 
@@ -112,6 +100,8 @@ mod test {
     use serde::Deserialize;
 
     use super::*;
+    use crate::{Error, Json};
+    use tokio::io::AsyncWriteExt;
 
     #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
     enum Status {
@@ -186,11 +176,142 @@ mod test {
         assert_eq!(None, server.read_frame().await.unwrap());
     }
 
-    //#[tokio::test]
-    //async fn test_bad_data() {
-    //    let (client, server) = tokio::io::duplex(1024);
-    //    let mut client = Connection::<_, Frame1, Frame1>::new(client, 1024);
-    //    let mut server = Connection::<_, Frame1, Frame1>::new(server, 1024);
-    //}
-    //
+    fn frame_1() -> Frame1 {
+        Frame1 {
+            id: 42,
+            name: "test".to_string(),
+            status: Status::Str("a status".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_split_frame() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let mut server = Connection::<_, Frame1, Frame1>::new(server, 1024);
+
+        let encoded_frame = rmp_serde::to_vec(&frame_1()).unwrap();
+        let mut bytes = (encoded_frame.len() as u32).to_be_bytes().to_vec();
+        bytes.extend(encoded_frame);
+        bytes.extend_from_within(..);
+
+        let writer = tokio::spawn(async move {
+            for byte in bytes {
+                client.write_u8(byte).await.unwrap();
+                client.flush().await.unwrap();
+            }
+        });
+        assert_eq!(frame_1(), server.read_frame().await.unwrap().unwrap());
+        assert_eq!(frame_1(), server.read_frame().await.unwrap().unwrap());
+        writer.await.unwrap();
+        assert_eq!(None, server.read_frame().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_split_connection() {
+        use futures::{SinkExt, StreamExt};
+
+        let (client, server) = tokio::io::duplex(64);
+        let client = Connection::<_, u32, u32>::new(client, 1024);
+        let server = Connection::<_, u32, u32>::new(server, 1024);
+        let (mut client_reader, mut client_writer) = client.into_split();
+        let (server_reader, mut server_writer) = server.into_split();
+
+        // The server echoes from another task, while the client is still writing
+        let echo = tokio::spawn(async move {
+            let mut frames = server_reader.map(|frame| frame.map(|frame| frame * 2));
+            server_writer.send_all(&mut frames).await.unwrap();
+            server_writer.close().await.unwrap();
+        });
+
+        let write = tokio::spawn(async move {
+            for frame in 0..1000 {
+                client_writer.feed(frame).await.unwrap();
+            }
+            client_writer.close().await.unwrap();
+        });
+
+        for frame in 0..1000 {
+            assert_eq!(
+                frame * 2,
+                client_reader.read_frame().await.unwrap().unwrap()
+            );
+        }
+        assert!(client_reader.next().await.is_none());
+        write.await.unwrap();
+        echo.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_json_connection() {
+        let (client, server) = tokio::io::duplex(1024);
+        let mut client = Connection::<_, Frame2, Frame1, Json>::new(client, 1024);
+        let mut server = Connection::<_, Frame1, Frame2, Json>::new(server, 1024);
+
+        client.write_frame(&frame_1()).await.unwrap();
+        assert_eq!(frame_1(), server.read_frame().await.unwrap().unwrap());
+
+        let frame_2 = Frame2 {
+            name: "".to_string(),
+            status: Status::Nb(10),
+        };
+        server.write_frame(&frame_2).await.unwrap();
+        assert_eq!(frame_2, client.read_frame().await.unwrap().unwrap());
+
+        // As typed by hand
+        let mut client = client.into_split().1.into_inner();
+        client
+            .write_all(b"\n{\"id\": 1, \"name\": \"a\", \"status\": \"None\"}\n")
+            .await
+            .unwrap();
+        let frame = server.read_frame().await.unwrap().unwrap();
+        assert_eq!(frame.status, Status::None);
+    }
+
+    #[tokio::test]
+    async fn test_frame_too_large() {
+        let (client, server) = tokio::io::duplex(1024);
+        let mut client = Connection::<_, Frame1, Frame1>::new(client, 1024).with_max_frame_size(8);
+        let mut server = Connection::<_, Frame1, Frame1>::new(server, 1024).with_max_frame_size(8);
+
+        assert!(matches!(
+            client.write_frame(&frame_1()).await,
+            Err(Error::FrameTooLarge { max: 8, .. })
+        ));
+
+        // Refused from the prefix alone, before any allocation
+        let mut client = client.into_split().1.into_inner();
+        client.write_all(&u32::MAX.to_be_bytes()).await.unwrap();
+        assert!(matches!(
+            server.read_frame().await,
+            Err(Error::FrameTooLarge {
+                size: 0xFFFF_FFFF,
+                max: 8
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_truncated_frame() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let mut server = Connection::<_, Frame1, Frame1>::new(server, 1024);
+
+        client.write_all(&[0, 0, 0, 10, 1, 2]).await.unwrap();
+        drop(client);
+        assert!(matches!(
+            server.read_frame().await,
+            Err(Error::ConnectionReset)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_frame() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let mut server = Connection::<_, Frame1, Frame1>::new(server, 1024);
+
+        client.write_all(&[0, 0, 0, 2, 0xc1, 0xc1]).await.unwrap();
+        assert!(matches!(
+            server.read_frame().await,
+            Err(Error::FailedToDecodeFrame(_))
+        ));
+    }
 }
@@ -1,15 +1,22 @@
 use std::{fmt::Display, io};
 
-pub type FrameEncodeError = rmp_serde::encode::Error;
-pub type FrameDecodeError = rmp_serde::decode::Error;
+/// Error of the library a `Codec` relies on
+pub type CodecError = Box<dyn core::error::Error + Send + Sync>;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
+    /// The stream ended in the middle of a frame
     ConnectionReset,
-    FailedToDecodeFrame(FrameDecodeError),
-    FailedToEncodeFrame(FrameEncodeError),
+    /// The size of a frame is above the maximum of the connection
+    FrameTooLarge {
+        size: usize,
+        max: usize,
+    },
+    /// A complete frame is not a valid encoding of the expected type
+    FailedToDecodeFrame(CodecError),
+    FailedToEncodeFrame(CodecError),
     FailedToReadFromStream(io::Error),
     FailedToWriteToStream(io::Error),
 }
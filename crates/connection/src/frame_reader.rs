@@ -0,0 +1,119 @@
+use std::{
+    future::poll_fn,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures::Stream;
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::{Codec, DEFAULT_MAX_FRAME_SIZE, Error, Result};
+
+/// Bytes read from the stream at once, at most
+const READ_CHUNK_SIZE: usize = 8192;
+
+/// Read half of a `Connection`, also usable on its own over any `AsyncRead`.
+#[derive(Debug)]
+pub struct FrameReader<Reader, InputFrame, C> {
+    reader: Reader,
+    buffer: Vec<u8>,
+    codec: C,
+    max_frame_size: usize,
+
+    _input_frame_type: PhantomData<InputFrame>,
+}
+
+impl<Reader, InputFrame, C> FrameReader<Reader, InputFrame, C>
+where
+    Reader: AsyncRead + Unpin,
+    InputFrame: DeserializeOwned,
+    C: Codec,
+{
+    pub fn new(reader: Reader, buffer_capacity: usize, codec: C) -> Self {
+        Self {
+            reader,
+            buffer: Vec::with_capacity(buffer_capacity),
+            codec,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+
+            _input_frame_type: PhantomData,
+        }
+    }
+
+    /// Sets the size above which the frames are refused, not counting what delimits them.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Returns `None` once the stream ended between two frames. Cancel safe.
+    pub async fn read_frame(&mut self) -> Result<Option<InputFrame>> {
+        poll_fn(|cx| self.poll_read_frame(cx)).await
+    }
+
+    fn poll_read_frame(&mut self, cx: &mut Context<'_>) -> Poll<Result<Option<InputFrame>>> {
+        loop {
+            if let Some(frame) = self.parse_frame()? {
+                return Poll::Ready(Ok(Some(frame)));
+            }
+
+            if 0 == ready!(self.poll_fill_buffer(cx)).map_err(Error::FailedToReadFromStream)? {
+                if self.buffer.is_empty() {
+                    return Poll::Ready(Ok(None));
+                } else {
+                    return Poll::Ready(Err(Error::ConnectionReset));
+                }
+            }
+        }
+    }
+
+    /// Appends what the stream has to the buffer. `0` indicates "end of stream".
+    fn poll_fill_buffer(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<usize>> {
+        // Read into a zeroed region past the bytes already in the buffer, cut back to what was
+        // actually read
+        let len = self.buffer.len();
+        self.buffer.resize(len + READ_CHUNK_SIZE, 0);
+
+        let mut read_buf = ReadBuf::new(&mut self.buffer[len..]);
+        let result = Pin::new(&mut self.reader).poll_read(cx, &mut read_buf);
+        let read = read_buf.filled().len();
+        self.buffer.truncate(len + read);
+
+        result.map_ok(|()| read)
+    }
+
+    fn parse_frame(&mut self) -> Result<Option<InputFrame>> {
+        match self.codec.decode(&self.buffer, self.max_frame_size) {
+            Ok((frame, size)) => {
+                self.buffer.drain(..size);
+                Ok(frame)
+            }
+            Err(error) => {
+                // Nothing after a frame that could not be decoded can be trusted
+                self.buffer.clear();
+                Err(error)
+            }
+        }
+    }
+
+    pub fn into_inner(self) -> Reader {
+        self.reader
+    }
+}
+
+impl<Reader, InputFrame, C> Stream for FrameReader<Reader, InputFrame, C>
+where
+    Reader: AsyncRead + Unpin,
+    InputFrame: DeserializeOwned,
+    C: Codec,
+{
+    type Item = Result<InputFrame>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_read_frame(cx).map(Result::transpose)
+    }
+}
+
+impl<Reader, InputFrame, C> Unpin for FrameReader<Reader, InputFrame, C> where Reader: Unpin {}
@@ -0,0 +1,135 @@
+use std::{
+    future::poll_fn,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures::Sink;
+use serde::Serialize;
+use tokio::io::AsyncWrite;
+
+use crate::{Codec, DEFAULT_MAX_FRAME_SIZE, Error, Result};
+
+/// Encoded frames above which `Sink::poll_ready` writes them before accepting another one
+const SINK_BUFFER_SIZE: usize = 8192;
+
+/// Write half of a `Connection`, also usable on its own over any `AsyncWrite`.
+#[derive(Debug)]
+pub struct FrameWriter<Writer, OutputFrame, C> {
+    writer: Writer,
+    /// Encoded frames not written yet
+    buffer: Vec<u8>,
+    /// Bytes of `buffer` already written
+    written: usize,
+    codec: C,
+    max_frame_size: usize,
+
+    _output_frame_type: PhantomData<OutputFrame>,
+}
+
+impl<Writer, OutputFrame, C> FrameWriter<Writer, OutputFrame, C>
+where
+    Writer: AsyncWrite + Unpin,
+    OutputFrame: Serialize,
+    C: Codec,
+{
+    pub fn new(writer: Writer, codec: C) -> Self {
+        Self {
+            writer,
+            buffer: Vec::new(),
+            written: 0,
+            codec,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+
+            _output_frame_type: PhantomData,
+        }
+    }
+
+    /// Sets the size above which the frames are refused, not counting what delimits them.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Writes `frame` and flushes the stream.
+    pub async fn write_frame(&mut self, frame: &OutputFrame) -> Result<()> {
+        self.encode(frame)?;
+        poll_fn(|cx| self.poll_flush_frames(cx)).await
+    }
+
+    /// Closes the write half of the stream, letting the other end read the end of stream. A TLS
+    /// stream also tells its peer the connection was not truncated.
+    pub async fn shutdown(&mut self) -> Result<()> {
+        poll_fn(|cx| self.poll_shutdown(cx)).await
+    }
+
+    fn encode(&mut self, frame: &OutputFrame) -> Result<()> {
+        self.codec
+            .encode(frame, &mut self.buffer, self.max_frame_size)
+    }
+
+    /// Writes the buffered frames, then flushes the stream.
+    fn poll_flush_frames(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        while self.written < self.buffer.len() {
+            let written =
+                ready!(Pin::new(&mut self.writer).poll_write(cx, &self.buffer[self.written..]))
+                    .map_err(Error::FailedToWriteToStream)?;
+            if written == 0 {
+                return Poll::Ready(Err(Error::FailedToWriteToStream(
+                    std::io::ErrorKind::WriteZero.into(),
+                )));
+            }
+            self.written += written;
+        }
+        self.buffer.clear();
+        self.written = 0;
+
+        Pin::new(&mut self.writer)
+            .poll_flush(cx)
+            .map_err(Error::FailedToWriteToStream)
+    }
+
+    fn poll_shutdown(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        ready!(self.poll_flush_frames(cx))?;
+        Pin::new(&mut self.writer)
+            .poll_shutdown(cx)
+            .map_err(Error::FailedToWriteToStream)
+    }
+
+    /// Frames buffered by the `Sink` implementation and not flushed yet are lost.
+    pub fn into_inner(self) -> Writer {
+        self.writer
+    }
+}
+
+impl<Writer, OutputFrame, C> Sink<OutputFrame> for FrameWriter<Writer, OutputFrame, C>
+where
+    Writer: AsyncWrite + Unpin,
+    OutputFrame: Serialize,
+    C: Codec,
+{
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        if this.buffer.len() < SINK_BUFFER_SIZE {
+            return Poll::Ready(Ok(()));
+        }
+        this.poll_flush_frames(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, frame: OutputFrame) -> Result<()> {
+        self.get_mut().encode(&frame)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().poll_flush_frames(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().poll_shutdown(cx)
+    }
+}
+
+impl<Writer, OutputFrame, C> Unpin for FrameWriter<Writer, OutputFrame, C> where Writer: Unpin {}
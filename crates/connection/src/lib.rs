@@ -1,6 +1,14 @@
+pub mod codec;
+pub use codec::{AnyCodec, Codec, Json, MessagePack};
+
 mod connection;
-pub use connection::Connection;
+pub use connection::{Connection, ConnectionReader, ConnectionWriter, DEFAULT_MAX_FRAME_SIZE};
+
+mod frame_reader;
+pub use frame_reader::FrameReader;
+
+mod frame_writer;
+pub use frame_writer::FrameWriter;
 
 mod error;
-pub(crate) use error::FrameDecodeError;
-pub use error::{Error, Result};
+pub use error::{CodecError, Error, Result};
@@ -1,3 +1,6 @@
+#[cfg(target_os = "linux")]
+pub mod pidfd;
+pub mod pwd;
 pub mod signal;
 pub mod sys;
 pub mod unistd;
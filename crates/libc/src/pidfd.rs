@@ -0,0 +1,8 @@
+use std::ffi::{c_int, c_uint};
+
+#[link(name = "c")]
+unsafe extern "C" {
+    /// File descriptor referring to process `pid`, readable once it exited. Reading it does not
+    /// reap the process.
+    pub fn pidfd_open(pid: crate::sys::types::Pid, flags: c_uint) -> c_int;
+}
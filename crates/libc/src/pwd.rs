@@ -0,0 +1,44 @@
+use std::ffi::{c_char, c_int, c_uint};
+
+#[allow(non_camel_case_types)]
+pub type uid_t = c_uint;
+#[allow(non_camel_case_types)]
+pub type gid_t = c_uint;
+
+/// Leading fields of `struct passwd`, laid out the same on Linux and macOS. Only ever read
+/// through the pointer returned by `getpwnam` or `getpwuid`.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct passwd {
+    pub pw_name: *mut c_char,
+    pub pw_passwd: *mut c_char,
+    pub pw_uid: uid_t,
+    pub pw_gid: gid_t,
+}
+
+/// Leading fields of `struct group`, only ever read through the pointer returned by `getgrnam`.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct group {
+    pub gr_name: *mut c_char,
+    pub gr_passwd: *mut c_char,
+    pub gr_gid: gid_t,
+}
+
+#[link(name = "c")]
+unsafe extern "C" {
+    pub fn getpwnam(name: *const c_char) -> *mut passwd;
+
+    pub fn getpwuid(uid: uid_t) -> *mut passwd;
+
+    pub fn getgrnam(name: *const c_char) -> *mut group;
+
+    /// Fills `groups` with the groups of `user` along with `group`. Returns -1 when more than
+    /// `ngroups` groups were found, `ngroups` being set to their number.
+    pub fn getgrouplist(
+        user: *const c_char,
+        group: gid_t,
+        groups: *mut gid_t,
+        ngroups: *mut c_int,
+    ) -> c_int;
+}
@@ -12,4 +12,6 @@ unsafe extern "C" {
     pub fn dup2(old_fd: c_int, new_fd: c_int) -> c_int;
 
     pub fn umask(cmask: mode_t) -> mode_t;
+
+    pub fn getuid() -> crate::pwd::uid_t;
 }
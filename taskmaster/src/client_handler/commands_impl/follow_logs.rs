@@ -0,0 +1,198 @@
+use commands::{ClientCommand, ServerCommand};
+
+use tokio::sync::oneshot;
+
+use crate::{
+    client_handler::{ClientHandler, Error, FrameSender, Result},
+    tasks_manager,
+};
+
+impl<TaskManager> ClientHandler<TaskManager>
+where
+    TaskManager: tasks_manager::Api,
+{
+    /// Streams the followed logs to the client until they end or until `cancelled`, and
+    /// returns the frame ending the stream.
+    pub(in crate::client_handler) async fn handle_follow_logs(
+        &self,
+        command: ServerCommand,
+        frames: &FrameSender,
+        mut cancelled: oneshot::Receiver<()>,
+    ) -> Result<ClientCommand> {
+        eprintln!("Client {} requested {command:?}", self.client_id);
+
+        let ServerCommand::FollowLogs { targets, streams } = &command else {
+            unreachable!("handle_follow_logs is only called with ServerCommand::FollowLogs");
+        };
+
+        let mut log_lines = match self
+            .task_manager
+            .follow_logs(targets.clone(), streams.clone())
+            .await
+        {
+            Ok(Ok(log_lines)) => log_lines,
+            Ok(Err(target)) => return Ok(ClientCommand::no_such_program(target)),
+            Err(error) => {
+                return Err(Error::HandleCommand {
+                    client_id: self.client_id,
+                    command: Box::new(command),
+                    error,
+                });
+            }
+        };
+
+        loop {
+            tokio::select! {
+                log_line = log_lines.recv() => match log_line {
+                    Some(log_line) => frames.send(ClientCommand::LogLine(log_line)).await,
+                    None => break,
+                },
+                _ = &mut cancelled => break,
+            }
+        }
+
+        Ok(ClientCommand::LogStreamEnded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::tasks_manager;
+
+    use crate::client_handler;
+    use commands::{LogLine, LogStream, Response, ServerCommand};
+    use mockall::predicate::eq;
+    use tokio::sync::mpsc;
+
+    fn log_line(message: &str) -> LogLine {
+        LogLine {
+            program: "nginx".to_string(),
+            instance: 0,
+            stream: LogStream::Stdout,
+            message: message.to_string(),
+        }
+    }
+
+    fn follow_nginx() -> ServerCommand {
+        ServerCommand::FollowLogs {
+            targets: vec!["nginx".to_string()],
+            streams: vec![LogStream::Stdout],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_follow_logs() {
+        let (sender, receiver) = mpsc::channel(10);
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_follow_logs()
+            .with(eq(vec!["nginx".to_string()]), eq(vec![LogStream::Stdout]))
+            .once()
+            .return_once(|_, _| Ok(Ok(receiver)));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&follow_nginx()).await.unwrap();
+        sender.send(log_line("line 1\n")).await.unwrap();
+        sender.send(log_line("line 2\n")).await.unwrap();
+        for message in ["line 1\n", "line 2\n"] {
+            let frame = client.read_frame().await.unwrap();
+            assert_eq!(frame, Some(ClientCommand::LogLine(log_line(message))));
+        }
+
+        drop(sender);
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame, Some(ClientCommand::LogStreamEnded));
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_follow_logs_alongside_requests() {
+        let (sender, receiver) = mpsc::channel(10);
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_follow_logs()
+            .once()
+            .return_once(|_, _| Ok(Ok(receiver)));
+        mock_task_manager
+            .expect_list_tasks()
+            .once()
+            .return_once(|| Ok(vec!["nginx".to_string()]));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        let id = client.write_frame(&follow_nginx()).await.unwrap();
+        let list_id = client.write_frame(&ServerCommand::ListTasks).await.unwrap();
+        let response = client.read_response().await.unwrap();
+        assert_eq!(
+            response,
+            Some(Response::new(
+                list_id,
+                ClientCommand::TaskList(vec!["nginx".to_string()])
+            ))
+        );
+
+        // The stream goes on
+        sender.send(log_line("line 1\n")).await.unwrap();
+        let response = client.read_response().await.unwrap();
+        assert_eq!(
+            response,
+            Some(Response::new(
+                id,
+                ClientCommand::LogLine(log_line("line 1\n"))
+            ))
+        );
+
+        let unfollow_id = client
+            .write_frame(&ServerCommand::UnfollowLogs { id })
+            .await
+            .unwrap();
+        let mut responses = vec![
+            client.read_response().await.unwrap().unwrap(),
+            client.read_response().await.unwrap().unwrap(),
+        ];
+        responses.sort_by_key(|response| response.id);
+        assert_eq!(
+            responses,
+            [
+                Response::new(id, ClientCommand::LogStreamEnded),
+                Response::new(unfollow_id, ClientCommand::LogStreamEnded),
+            ]
+        );
+
+        // The stream is already gone
+        let unfollow_id = client
+            .write_frame(&ServerCommand::UnfollowLogs { id })
+            .await
+            .unwrap();
+        assert_eq!(
+            client.read_response().await.unwrap(),
+            Some(Response::new(unfollow_id, ClientCommand::LogStreamEnded))
+        );
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_follow_logs_no_such_program() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_follow_logs()
+            .once()
+            .return_once(|_, _| Ok(Err("nginx".to_string())));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&follow_nginx()).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::no_such_program("nginx".to_string()))
+        );
+
+        server.check_errors(client).await;
+    }
+}
@@ -1,20 +1,18 @@
 use commands::{ClientCommand, ServerCommand};
-use tokio::io::{AsyncRead, AsyncWrite};
 
 use crate::{
     client_handler::{ClientHandler, Error, Result},
     tasks_manager,
 };
 
-impl<Stream, TaskManager> ClientHandler<Stream, TaskManager>
+impl<TaskManager> ClientHandler<TaskManager>
 where
-    Stream: AsyncWrite + AsyncRead + Unpin,
     TaskManager: tasks_manager::Api,
 {
     pub(in crate::client_handler) async fn handle_list_tasks(
-        &mut self,
+        &self,
         command: ServerCommand,
-    ) -> Result<()> {
+    ) -> Result<ClientCommand> {
         eprintln!("Client {} requested ListTasks", self.client_id);
 
         let task_list =
@@ -23,14 +21,14 @@ where
                 .await
                 .map_err(|error| Error::HandleCommand {
                     client_id: self.client_id,
-                    command,
+                    command: Box::new(command),
                     error,
                 })?;
 
         let response = ClientCommand::TaskList(task_list);
 
         eprintln!("Client {} ListTasks response: {response:?}", self.client_id);
-        self.write_frame(&response).await
+        Ok(response)
     }
 }
 
@@ -1 +1,9 @@
+mod follow_logs;
 mod list_tasks;
+mod reopen_logs;
+mod signal;
+mod start;
+mod stop;
+mod subscribe;
+mod tail;
+mod wait;
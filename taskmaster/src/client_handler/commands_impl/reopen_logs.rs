@@ -0,0 +1,59 @@
+use commands::{ClientCommand, ServerCommand};
+
+use crate::{
+    client_handler::{ClientHandler, Error, Result},
+    tasks_manager,
+};
+
+impl<TaskManager> ClientHandler<TaskManager>
+where
+    TaskManager: tasks_manager::Api,
+{
+    pub(in crate::client_handler) async fn handle_reopen_logs(
+        &self,
+        command: ServerCommand,
+    ) -> Result<ClientCommand> {
+        eprintln!("Client {} requested ReopenLogs", self.client_id);
+
+        self.task_manager
+            .reopen_logs()
+            .await
+            .map_err(|error| Error::HandleCommand {
+                client_id: self.client_id,
+                command: Box::new(command),
+                error,
+            })?;
+
+        Ok(ClientCommand::LogsReopened)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::tasks_manager;
+
+    use crate::client_handler;
+    use commands::ServerCommand;
+
+    #[tokio::test]
+    async fn test_handle_reopen_logs() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_reopen_logs()
+            .once()
+            .return_once(|| Ok(()));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client
+            .write_frame(&ServerCommand::ReopenLogs)
+            .await
+            .unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame, Some(ClientCommand::LogsReopened));
+
+        server.check_errors(client).await;
+    }
+}
@@ -0,0 +1,146 @@
+use commands::{ClientCommand, ErrorKind, ServerCommand};
+
+use crate::{
+    client_handler::{ClientHandler, Error, Result},
+    config::program::parse_signal,
+    tasks_manager,
+};
+
+impl<TaskManager> ClientHandler<TaskManager>
+where
+    TaskManager: tasks_manager::Api,
+{
+    pub(in crate::client_handler) async fn handle_signal(
+        &self,
+        command: ServerCommand,
+    ) -> Result<ClientCommand> {
+        eprintln!("Client {} requested {command:?}", self.client_id);
+
+        let ServerCommand::Signal {
+            target,
+            signal,
+            process_group,
+        } = &command
+        else {
+            unreachable!("handle_signal is only called with ServerCommand::Signal");
+        };
+
+        let Ok(parsed_signal) = parse_signal(signal) else {
+            return Ok(ClientCommand::error(
+                ErrorKind::InvalidSignal,
+                format!("Invalid signal: `{signal}`"),
+                Some(target.clone()),
+            ));
+        };
+
+        let response = match self
+            .task_manager
+            .signal(target.clone(), parsed_signal, *process_group)
+            .await
+        {
+            Ok(Some(results)) => ClientCommand::SignalSent(results),
+            Ok(None) => ClientCommand::no_such_program(target.clone()),
+            Err(error) => {
+                return Err(Error::HandleCommand {
+                    client_id: self.client_id,
+                    command: Box::new(command),
+                    error,
+                });
+            }
+        };
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::tasks_manager;
+
+    use crate::client_handler;
+    use commands::SignalResult;
+    use mockall::predicate::eq;
+    use signal::Signal;
+
+    fn signal_nginx(signal: &str) -> ServerCommand {
+        ServerCommand::Signal {
+            target: "nginx".to_string(),
+            signal: signal.to_string(),
+            process_group: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_signal() {
+        let results = vec![
+            SignalResult {
+                program: "nginx".to_string(),
+                instance: 0,
+                error: None,
+            },
+            SignalResult {
+                program: "nginx".to_string(),
+                instance: 1,
+                error: Some("not running".to_string()),
+            },
+        ];
+        let response = results.clone();
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_signal()
+            .with(eq("nginx".to_string()), eq(Signal::SIGHUP), eq(false))
+            .once()
+            .return_once(|_, _, _| Ok(Some(response)));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&signal_nginx("HUP")).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame, Some(ClientCommand::SignalSent(results)));
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_signal_invalid_signal() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager.expect_signal().never();
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&signal_nginx("SIGNOPE")).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::error(
+                ErrorKind::InvalidSignal,
+                "Invalid signal: `SIGNOPE`",
+                Some("nginx".to_string())
+            ))
+        );
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_signal_no_such_program() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_signal()
+            .once()
+            .return_once(|_, _, _| Ok(None));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&signal_nginx("USR1")).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::no_such_program("nginx".to_string()))
+        );
+
+        server.check_errors(client).await;
+    }
+}
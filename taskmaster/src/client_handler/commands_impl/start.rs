@@ -0,0 +1,164 @@
+use commands::{ClientCommand, ErrorKind, ServerCommand};
+
+use crate::{
+    client_handler::{ClientHandler, Error, Result},
+    tasks_manager::{self, StartOutcome},
+};
+
+impl<TaskManager> ClientHandler<TaskManager>
+where
+    TaskManager: tasks_manager::Api,
+{
+    pub(in crate::client_handler) async fn handle_start(
+        &self,
+        command: ServerCommand,
+    ) -> Result<ClientCommand> {
+        eprintln!("Client {} requested {command:?}", self.client_id);
+
+        let ServerCommand::Start { target, wait } = &command else {
+            unreachable!("handle_start is only called with ServerCommand::Start");
+        };
+
+        let target = target.clone();
+        let response = match self.task_manager.start(target.clone(), *wait).await {
+            Ok(Some(StartOutcome::Started)) => ClientCommand::Started { target },
+            Ok(Some(StartOutcome::Failed)) => ClientCommand::error(
+                ErrorKind::StartFailed,
+                format!("`{target}` did not start"),
+                Some(target),
+            ),
+            Ok(Some(StartOutcome::AlreadyRunning)) => ClientCommand::error(
+                ErrorKind::AlreadyRunning,
+                format!("`{target}` is already running"),
+                Some(target),
+            ),
+            Ok(Some(StartOutcome::Stopping)) => ClientCommand::error(
+                ErrorKind::AlreadyRunning,
+                format!("`{target}` is still stopping"),
+                Some(target),
+            ),
+            Ok(None) => ClientCommand::no_such_program(target),
+            Err(error) => {
+                return Err(Error::HandleCommand {
+                    client_id: self.client_id,
+                    command: Box::new(command),
+                    error,
+                });
+            }
+        };
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::tasks_manager;
+
+    use crate::client_handler;
+    use mockall::predicate::eq;
+
+    fn start_nginx(wait: bool) -> ServerCommand {
+        ServerCommand::Start {
+            target: "nginx".to_string(),
+            wait,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_start() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_start()
+            .with(eq("nginx".to_string()), eq(true))
+            .once()
+            .return_once(|_, _| Ok(Some(StartOutcome::Started)));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&start_nginx(true)).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::Started {
+                target: "nginx".to_string()
+            })
+        );
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_start_failed() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_start()
+            .once()
+            .return_once(|_, _| Ok(Some(StartOutcome::Failed)));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&start_nginx(true)).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::error(
+                ErrorKind::StartFailed,
+                "`nginx` did not start",
+                Some("nginx".to_string())
+            ))
+        );
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_start_already_running() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_start()
+            .once()
+            .return_once(|_, _| Ok(Some(StartOutcome::AlreadyRunning)));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&start_nginx(false)).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::error(
+                ErrorKind::AlreadyRunning,
+                "`nginx` is already running",
+                Some("nginx".to_string())
+            ))
+        );
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_start_stopping() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_start()
+            .once()
+            .return_once(|_, _| Ok(Some(StartOutcome::Stopping)));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&start_nginx(false)).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::error(
+                ErrorKind::AlreadyRunning,
+                "`nginx` is still stopping",
+                Some("nginx".to_string())
+            ))
+        );
+
+        server.check_errors(client).await;
+    }
+}
@@ -0,0 +1,113 @@
+use commands::{ClientCommand, ServerCommand};
+
+use crate::{
+    client_handler::{ClientHandler, Error, Result},
+    tasks_manager,
+};
+
+impl<TaskManager> ClientHandler<TaskManager>
+where
+    TaskManager: tasks_manager::Api,
+{
+    pub(in crate::client_handler) async fn handle_stop(
+        &self,
+        command: ServerCommand,
+    ) -> Result<ClientCommand> {
+        eprintln!("Client {} requested {command:?}", self.client_id);
+
+        let ServerCommand::Stop {
+            target,
+            timeout,
+            kill,
+        } = &command
+        else {
+            unreachable!("handle_stop is only called with ServerCommand::Stop");
+        };
+
+        let target = target.clone();
+        let response = match self
+            .task_manager
+            .stop(target.clone(), *timeout, *kill)
+            .await
+        {
+            Ok(true) => ClientCommand::Stopped { target },
+            Ok(false) => ClientCommand::no_such_program(target),
+            Err(error) => {
+                return Err(Error::HandleCommand {
+                    client_id: self.client_id,
+                    command: Box::new(command),
+                    error,
+                });
+            }
+        };
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::tasks_manager;
+
+    use crate::client_handler;
+    use mockall::predicate::eq;
+
+    #[tokio::test]
+    async fn test_handle_stop() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_stop()
+            .with(eq("nginx:1".to_string()), eq(Some(5)), eq(true))
+            .once()
+            .return_once(|_, _, _| Ok(true));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client
+            .write_frame(&ServerCommand::Stop {
+                target: "nginx:1".to_string(),
+                timeout: Some(5),
+                kill: true,
+            })
+            .await
+            .unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::Stopped {
+                target: "nginx:1".to_string()
+            })
+        );
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_stop_no_such_program() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_stop()
+            .once()
+            .return_once(|_, _, _| Ok(false));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client
+            .write_frame(&ServerCommand::Stop {
+                target: "nginx".to_string(),
+                timeout: None,
+                kill: false,
+            })
+            .await
+            .unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::no_such_program("nginx".to_string()))
+        );
+
+        server.check_errors(client).await;
+    }
+}
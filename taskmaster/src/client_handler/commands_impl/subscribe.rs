@@ -0,0 +1,130 @@
+use commands::{ClientCommand, ServerCommand};
+
+use tokio::sync::oneshot;
+
+use crate::{
+    client_handler::{ClientHandler, Error, FrameSender, Result},
+    tasks_manager,
+};
+
+impl<TaskManager> ClientHandler<TaskManager>
+where
+    TaskManager: tasks_manager::Api,
+{
+    /// Streams the events the client subscribed to until `cancelled`, and returns the frame
+    /// ending the stream.
+    pub(in crate::client_handler) async fn handle_subscribe(
+        &self,
+        command: ServerCommand,
+        frames: &FrameSender,
+        mut cancelled: oneshot::Receiver<()>,
+    ) -> Result<ClientCommand> {
+        eprintln!("Client {} requested {command:?}", self.client_id);
+
+        let ServerCommand::Subscribe { filter } = &command else {
+            unreachable!("handle_subscribe is only called with ServerCommand::Subscribe");
+        };
+
+        let mut events = match self.task_manager.subscribe(filter.clone()).await {
+            Ok(events) => events,
+            Err(error) => {
+                return Err(Error::HandleCommand {
+                    client_id: self.client_id,
+                    command: Box::new(command),
+                    error,
+                });
+            }
+        };
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Some(event) => frames.send(ClientCommand::Event(event)).await,
+                    None => break,
+                },
+                _ = &mut cancelled => break,
+            }
+        }
+
+        Ok(ClientCommand::EventStreamEnded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::tasks_manager;
+
+    use crate::client_handler;
+    use commands::{Event, EventFilter, EventKind, ProcessStatus, Response};
+    use mockall::predicate::eq;
+    use tokio::sync::mpsc;
+
+    fn status_filter() -> EventFilter {
+        EventFilter {
+            kinds: vec![EventKind::Status],
+            programs: vec!["nginx".to_string()],
+        }
+    }
+
+    fn status_event(status: ProcessStatus) -> Event {
+        Event::Status {
+            program: "nginx".to_string(),
+            instance: 0,
+            status,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_subscribe() {
+        let (sender, receiver) = mpsc::channel(10);
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_subscribe()
+            .with(eq(status_filter()))
+            .once()
+            .return_once(|_| Ok(receiver));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        let id = client
+            .write_frame(&ServerCommand::Subscribe {
+                filter: status_filter(),
+            })
+            .await
+            .unwrap();
+        sender
+            .send(status_event(ProcessStatus::Starting))
+            .await
+            .unwrap();
+        sender
+            .send(status_event(ProcessStatus::Running))
+            .await
+            .unwrap();
+        for status in [ProcessStatus::Starting, ProcessStatus::Running] {
+            let frame = client.read_frame().await.unwrap();
+            assert_eq!(frame, Some(ClientCommand::Event(status_event(status))));
+        }
+
+        let unsubscribe_id = client
+            .write_frame(&ServerCommand::Unsubscribe { id })
+            .await
+            .unwrap();
+        // Both the stream and the request ending it are answered, in any order
+        let mut responses = vec![
+            client.read_response().await.unwrap().unwrap(),
+            client.read_response().await.unwrap().unwrap(),
+        ];
+        responses.sort_by_key(|response| response.id);
+        assert_eq!(
+            responses,
+            [
+                Response::new(id, ClientCommand::EventStreamEnded),
+                Response::new(unsubscribe_id, ClientCommand::EventStreamEnded),
+            ]
+        );
+
+        server.check_errors(client).await;
+    }
+}
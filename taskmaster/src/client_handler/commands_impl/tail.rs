@@ -0,0 +1,120 @@
+use commands::{ClientCommand, ServerCommand};
+
+use crate::{
+    client_handler::{ClientHandler, Error, Result},
+    tasks_manager,
+};
+
+impl<TaskManager> ClientHandler<TaskManager>
+where
+    TaskManager: tasks_manager::Api,
+{
+    pub(in crate::client_handler) async fn handle_tail(
+        &self,
+        command: ServerCommand,
+    ) -> Result<ClientCommand> {
+        eprintln!("Client {} requested {command:?}", self.client_id);
+
+        let ServerCommand::Tail {
+            target,
+            lines,
+            stream,
+        } = &command
+        else {
+            unreachable!("handle_tail is only called with ServerCommand::Tail");
+        };
+
+        let response = match self
+            .task_manager
+            .tail(target.clone(), *lines, *stream)
+            .await
+        {
+            Ok(Some(log_lines)) => ClientCommand::LogLines(log_lines),
+            Ok(None) => ClientCommand::no_such_program(target.clone()),
+            Err(error) => {
+                return Err(Error::HandleCommand {
+                    client_id: self.client_id,
+                    command: Box::new(command),
+                    error,
+                });
+            }
+        };
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::tasks_manager;
+
+    use crate::client_handler;
+    use commands::{LogLine, LogStream, ServerCommand};
+    use mockall::predicate::eq;
+
+    #[tokio::test]
+    async fn test_handle_tail() {
+        let expected = vec![LogLine {
+            program: "nginx".to_string(),
+            instance: 1,
+            stream: LogStream::Stderr,
+            message: "Hello taskmaster\n".to_string(),
+        }];
+
+        let expected_clone = expected.clone();
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_tail()
+            .with(
+                eq("nginx:1".to_string()),
+                eq(10),
+                eq(Some(LogStream::Stderr)),
+            )
+            .once()
+            .return_once(|_, _, _| Ok(Some(expected_clone)));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client
+            .write_frame(&ServerCommand::Tail {
+                target: "nginx:1".to_string(),
+                lines: 10,
+                stream: Some(LogStream::Stderr),
+            })
+            .await
+            .unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame, Some(ClientCommand::LogLines(expected)));
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_tail_no_such_program() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_tail()
+            .once()
+            .return_once(|_, _, _| Ok(None));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client
+            .write_frame(&ServerCommand::Tail {
+                target: "unknown".to_string(),
+                lines: 10,
+                stream: None,
+            })
+            .await
+            .unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::no_such_program("unknown".to_string()))
+        );
+
+        server.check_errors(client).await;
+    }
+}
@@ -0,0 +1,106 @@
+use commands::{ClientCommand, ErrorKind, ServerCommand};
+
+use crate::{
+    client_handler::{ClientHandler, Error, Result},
+    tasks_manager,
+};
+
+impl<TaskManager> ClientHandler<TaskManager>
+where
+    TaskManager: tasks_manager::Api,
+{
+    pub(in crate::client_handler) async fn handle_wait(
+        &self,
+        command: ServerCommand,
+    ) -> Result<ClientCommand> {
+        eprintln!("Client {} requested {command:?}", self.client_id);
+
+        let ServerCommand::Wait { target, timeout } = &command else {
+            unreachable!("handle_wait is only called with ServerCommand::Wait");
+        };
+
+        let target = target.clone();
+        let response = match self.task_manager.wait(target.clone(), *timeout).await {
+            Ok(Some(true)) => ClientCommand::Ready { target },
+            Ok(Some(false)) => ClientCommand::error(
+                ErrorKind::NotReady,
+                format!("`{target}` is not ready"),
+                Some(target),
+            ),
+            Ok(None) => ClientCommand::no_such_program(target),
+            Err(error) => {
+                return Err(Error::HandleCommand {
+                    client_id: self.client_id,
+                    command: Box::new(command),
+                    error,
+                });
+            }
+        };
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::tasks_manager;
+
+    use crate::client_handler;
+    use mockall::predicate::eq;
+
+    fn wait_nginx() -> ServerCommand {
+        ServerCommand::Wait {
+            target: "nginx".to_string(),
+            timeout: Some(3),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_wait() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_wait()
+            .with(eq("nginx".to_string()), eq(Some(3)))
+            .once()
+            .return_once(|_, _| Ok(Some(true)));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&wait_nginx()).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::Ready {
+                target: "nginx".to_string()
+            })
+        );
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_wait_not_ready() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_wait()
+            .once()
+            .return_once(|_, _| Ok(Some(false)));
+
+        let (mut client, server) = client_handler::test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&wait_nginx()).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::error(
+                ErrorKind::NotReady,
+                "`nginx` is not ready",
+                Some("nginx".to_string())
+            ))
+        );
+
+        server.check_errors(client).await;
+    }
+}
@@ -8,6 +8,12 @@ pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
+    #[allow(dead_code)]
+    PickCodec {
+        client_id: ClientId,
+        error: std::io::Error,
+    },
+
     #[allow(dead_code)]
     ReadFrame {
         client_id: ClientId,
@@ -20,10 +26,16 @@ pub enum Error {
         error: connection::Error,
     },
 
+    #[allow(dead_code)]
+    GenerateNonce {
+        client_id: ClientId,
+        error: getrandom::Error,
+    },
+
     #[allow(dead_code)]
     HandleCommand {
         client_id: ClientId,
-        command: ServerCommand,
+        command: Box<ServerCommand>,
         error: tasks_manager::Error,
     },
 }
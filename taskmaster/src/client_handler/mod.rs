@@ -9,10 +9,52 @@ pub use error::Error;
 use error::Result;
 
 use crate::tasks_manager;
-use commands::{ClientCommand, ServerCommand};
-use connection::Connection;
-use std::os::fd::RawFd;
-use tokio::io::{AsyncRead, AsyncWrite};
+use commands::{
+    ClientCommand, ErrorKind, Feature, Hello, IncomingRequest, PROTOCOL_VERSION, Request,
+    RequestId, Response, Role, ServerCommand, auth,
+};
+use connection::{AnyCodec, Codec, Connection, Json, MessagePack};
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::{collections::HashMap, io, os::fd::RawFd, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    sync::{mpsc, oneshot},
+};
+
+/// Time a client has to answer the authentication challenge
+const AUTHENTICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time a client has to send the name of its codec
+const CODEC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest line naming a codec
+const MAX_CODEC_NAME_LEN: usize = 16;
+
+/// Requests of a client handled at the same time, the next ones are only read once one of them
+/// is answered. Streams are not counted, they last until the client ends them.
+const MAX_REQUESTS_IN_FLIGHT: usize = 32;
+
+/// Responses waiting to be written to the client
+const RESPONSES_CAPACITY: usize = 64;
+
+/// Features of the commands handled by `ClientHandler::handle_command`
+const FEATURES: &[Feature] = &[
+    Feature::StartStop,
+    Feature::ReopenLogs,
+    Feature::Tail,
+    Feature::FollowLogs,
+    Feature::Events,
+    Feature::Signal,
+    Feature::Wait,
+];
+
+fn hello() -> ClientCommand {
+    ClientCommand::Hello(Hello {
+        protocol_version: PROTOCOL_VERSION,
+        features: FEATURES.to_vec(),
+        server_version: env!("CARGO_PKG_VERSION").to_string(),
+    })
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct ClientId(RawFd);
@@ -29,61 +71,426 @@ impl std::fmt::Display for ClientId {
     }
 }
 
-pub struct ClientHandler<Stream, TaskManager> {
+/// Sends the frames of a streaming request to the client, tagged with the id of the request.
+pub(in crate::client_handler) struct FrameSender {
+    id: RequestId,
+    responses: mpsc::Sender<Response>,
+}
+
+impl FrameSender {
+    pub(in crate::client_handler) async fn send(&self, command: ClientCommand) {
+        // The receiver lives as long as the requests, nobody is left to tell if it is gone
+        let _ = self.responses.send(Response::new(self.id, command)).await;
+    }
+}
+
+/// A request being handled.
+struct InFlight {
+    /// Feature of the stream the request is, so that only the matching command ends it
+    stream: Option<Feature>,
+    /// Ends the stream when used or dropped
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+/// What to do with a request that was just read.
+enum Dispatch {
+    Respond(Response),
+    /// The request is handled along with the others, `Receiver` telling when to end its stream
+    Handle(Request, oneshot::Receiver<()>),
+}
+
+pub struct ClientHandler<TaskManager> {
     client_id: ClientId,
+    /// The client cannot send any command when it has no role
+    role: Option<Role>,
     task_manager: TaskManager,
-    connection: Connection<Stream, ServerCommand, ClientCommand>,
 }
 
-impl<Stream, TaskManager> ClientHandler<Stream, TaskManager>
+impl<TaskManager> ClientHandler<TaskManager>
 where
-    Stream: AsyncWrite + AsyncRead + Unpin,
     TaskManager: tasks_manager::Api,
 {
-    pub async fn process_client(
-        socket: Stream,
+    pub async fn process_client<Stream>(
+        mut socket: Stream,
         task_manager: TaskManager,
         client_id: ClientId,
-    ) -> Result<()> {
-        let mut handler = Self::new(socket, task_manager, client_id)?;
+        role: Option<Role>,
+        secret: Option<&[u8]>,
+    ) -> Result<()>
+    where
+        Stream: AsyncWrite + AsyncRead + Unpin,
+    {
+        let handler = Self::new(task_manager, client_id, role);
+        let Some(codec) = pick_codec(&mut socket, client_id).await? else {
+            return Ok(());
+        };
+        let mut connection = ClientConnection {
+            client_id,
+            connection: Connection::with_codec(socket, 4096, codec),
+        };
 
-        handler
-            .write_frame(&ClientCommand::SuccessfulConnection)
+        if let Some(secret) = secret
+            && !connection.authenticate(secret).await?
+        {
+            eprintln!("Client {} failed to authenticate", handler.client_id);
+            return Ok(());
+        }
+
+        connection
+            .write_frame(&Response::unsolicited(hello()))
             .await?;
+        let _ = handler
+            .task_manager
+            .client_connected(client_id.0)
+            .await
+            .inspect_err(|err| eprintln!("Taskmaster error: {err}"));
 
-        handler.event_loop().await
+        handler.event_loop(connection).await
     }
 
-    fn new(socket: Stream, task_manager: TaskManager, client_id: ClientId) -> Result<Self> {
+    fn new(task_manager: TaskManager, client_id: ClientId, role: Option<Role>) -> Self {
         let handler = Self {
             client_id,
+            role,
             task_manager,
-            connection: Connection::new(socket, 4096),
         };
 
-        eprintln!("Client {} has connected", handler.client_id);
-        Ok(handler)
+        eprintln!(
+            "Client {} has connected with role {:?}",
+            handler.client_id, handler.role
+        );
+        handler
     }
 
-    async fn event_loop(mut self) -> Result<()> {
-        while let Some(command) = self.read_frame().await? {
-            #[allow(clippy::single_match)]
-            //TODO: remove once other commands than ListTasks are available
-            match command {
-                ServerCommand::ListTasks => self.handle_list_tasks(command).await?,
-                _ => {
-                    todo!()
+    /// Reads the requests and handles them concurrently, while their responses are written as
+    /// they come. Once the client is done sending requests, its streams are ended and the other
+    /// requests are still answered.
+    async fn event_loop<Stream>(&self, connection: ClientConnection<Stream>) -> Result<()>
+    where
+        Stream: AsyncWrite + AsyncRead + Unpin,
+    {
+        let client_id = connection.client_id;
+        let (mut reader, mut writer) = connection.connection.into_split();
+        let (sender, mut responses) = mpsc::channel(RESPONSES_CAPACITY);
+
+        // Each of them drops its half of the channel when done, ending the other one. Returns
+        // whether the client was done sending requests.
+        let read_requests = async move {
+            let mut requests = FuturesUnordered::new();
+            let mut in_flight = HashMap::new();
+            let mut reading = true;
+            let mut result = Ok(());
+
+            while reading || !requests.is_empty() {
+                let accepting = reading
+                    && in_flight
+                        .values()
+                        .filter(|request: &&InFlight| request.stream.is_none())
+                        .count()
+                        < MAX_REQUESTS_IN_FLIGHT;
+                tokio::select! {
+                    request = reader.read_frame(), if accepting => match request {
+                        Ok(Some(request)) => match self.dispatch(request, &mut in_flight) {
+                            Dispatch::Respond(response) => {
+                                let _ = sender.send(response).await;
+                            }
+                            Dispatch::Handle(request, cancelled) => {
+                                requests.push(self.handle_request(request, sender.clone(), cancelled));
+                            }
+                        },
+                        Ok(None) => {
+                            reading = false;
+                            in_flight.clear();
+                        }
+                        // The requests already read are still answered
+                        Err(error) => {
+                            let _ = sender
+                                .send(Response::unsolicited(ClientCommand::FailedToParseFrame))
+                                .await;
+                            reading = false;
+                            in_flight.clear();
+                            result = Err(Error::ReadFrame { client_id, error });
+                        }
+                    },
+                    Some(id) = requests.next() => {
+                        in_flight.remove(&id);
+                    },
+                    // Writing failed, the error is the one of `write_responses`
+                    () = sender.closed() => {
+                        if !requests.is_empty() {
+                            eprintln!(
+                                "Client {client_id}: Aborting {} requests that cannot be answered",
+                                requests.len()
+                            );
+                        }
+                        break;
+                    }
                 }
             }
+            result.map(|()| !reading)
+        };
+
+        let write_responses = async move {
+            while let Some(response) = responses.recv().await {
+                writer
+                    .write_frame(&response)
+                    .await
+                    .map_err(|error| Error::WriteFrame { client_id, error })?;
+            }
+            Ok(())
+        };
+
+        let (read, written) = tokio::join!(read_requests, write_responses);
+        match read? {
+            // A client done sending requests may have hung up without waiting for the responses
+            true => Ok(()),
+            false => written,
         }
-        Ok(())
     }
 
-    async fn read_frame(&mut self) -> Result<Option<ServerCommand>> {
+    /// Answers right away the requests that cannot be handled or that end a stream, and tracks
+    /// the others.
+    fn dispatch(
+        &self,
+        request: IncomingRequest,
+        in_flight: &mut HashMap<RequestId, InFlight>,
+    ) -> Dispatch {
+        let IncomingRequest { id, command } = request;
+        let Some(command) = command else {
+            eprintln!("Client {} sent an unknown command", self.client_id);
+            return Dispatch::Respond(Response::new(
+                id,
+                ClientCommand::error(
+                    ErrorKind::UnsupportedCommand,
+                    "The server does not know this command",
+                    None,
+                ),
+            ));
+        };
+
+        let required_role = command.required_role();
+        if self.role.is_none_or(|role| role < required_role) {
+            eprintln!(
+                "Client {} is not allowed to send {command:?}",
+                self.client_id
+            );
+            let target = command.target().map(str::to_string);
+            return Dispatch::Respond(Response::new(
+                id,
+                ClientCommand::error(
+                    ErrorKind::PermissionDenied,
+                    format!("The command requires the `{required_role:?}` role"),
+                    target,
+                ),
+            ));
+        }
+
+        let response = match command {
+            ServerCommand::UnfollowLogs { id: stream }
+            | ServerCommand::Unsubscribe { id: stream } => {
+                // The stream may have already ended on its own
+                if let Some(request) = in_flight.get_mut(&stream)
+                    && request.stream == command.required_feature()
+                    && let Some(cancel) = request.cancel.take()
+                {
+                    let _ = cancel.send(());
+                }
+                match command {
+                    ServerCommand::UnfollowLogs { .. } => ClientCommand::LogStreamEnded,
+                    _ => ClientCommand::EventStreamEnded,
+                }
+            }
+            command if in_flight.contains_key(&id) => ClientCommand::error(
+                ErrorKind::DuplicateRequest,
+                format!("Request {id} is still in flight"),
+                command.target().map(str::to_string),
+            ),
+            command => {
+                let (cancel, cancelled) = oneshot::channel();
+                let stream = match command {
+                    ServerCommand::FollowLogs { .. } | ServerCommand::Subscribe { .. } => {
+                        command.required_feature()
+                    }
+                    _ => None,
+                };
+                in_flight.insert(
+                    id,
+                    InFlight {
+                        stream,
+                        cancel: Some(cancel),
+                    },
+                );
+                return Dispatch::Handle(Request { id, command }, cancelled);
+            }
+        };
+        Dispatch::Respond(Response::new(id, response))
+    }
+
+    /// Handles a request and sends its last response. Returns the id of the request.
+    async fn handle_request(
+        &self,
+        request: Request,
+        responses: mpsc::Sender<Response>,
+        cancelled: oneshot::Receiver<()>,
+    ) -> RequestId {
+        let Request { id, command } = request;
+        let frames = FrameSender { id, responses };
+
+        // A failing command does not end the session, only a broken connection does
+        let response = match self.handle_command(command, &frames, cancelled).await {
+            Ok(response) => response,
+            Err(Error::HandleCommand { command, error, .. }) => {
+                eprintln!(
+                    "Client {} failed to handle {command:?}: {error}",
+                    self.client_id
+                );
+                ClientCommand::error(
+                    ErrorKind::Internal,
+                    error.to_string(),
+                    command.target().map(str::to_string),
+                )
+            }
+            Err(error) => {
+                eprintln!(
+                    "Client {} failed to handle request {id}: {error}",
+                    self.client_id
+                );
+                ClientCommand::error(ErrorKind::Internal, error.to_string(), None)
+            }
+        };
+        frames.send(response).await;
+        id
+    }
+
+    async fn handle_command(
+        &self,
+        command: ServerCommand,
+        frames: &FrameSender,
+        cancelled: oneshot::Receiver<()>,
+    ) -> Result<ClientCommand> {
+        match command {
+            // The client is already authenticated if it had to
+            ServerCommand::Authenticate { .. } => Ok(hello()),
+            ServerCommand::ListTasks => self.handle_list_tasks(command).await,
+            ServerCommand::ReopenLogs => self.handle_reopen_logs(command).await,
+            ServerCommand::Tail { .. } => self.handle_tail(command).await,
+            ServerCommand::FollowLogs { .. } => {
+                self.handle_follow_logs(command, frames, cancelled).await
+            }
+            ServerCommand::Subscribe { .. } => {
+                self.handle_subscribe(command, frames, cancelled).await
+            }
+            ServerCommand::Signal { .. } => self.handle_signal(command).await,
+            ServerCommand::Start { .. } => self.handle_start(command).await,
+            ServerCommand::Stop { .. } => self.handle_stop(command).await,
+            ServerCommand::Wait { .. } => self.handle_wait(command).await,
+            ServerCommand::Restart { .. } => Ok(ClientCommand::error(
+                ErrorKind::UnsupportedCommand,
+                "The server does not support this command",
+                command.target().map(str::to_string),
+            )),
+            ServerCommand::UnfollowLogs { .. } | ServerCommand::Unsubscribe { .. } => {
+                unreachable!("Ending a stream is handled by ClientHandler::dispatch")
+            }
+        }
+    }
+}
+
+impl<TaskManager> Drop for ClientHandler<TaskManager> {
+    fn drop(&mut self) {
+        eprintln!("Client {} has disconnected", self.client_id);
+    }
+}
+
+/// Reads the line naming the codec the client picked, see `connection::codec`. The client is
+/// told when the codec is unknown and should then be disconnected.
+async fn pick_codec<Stream>(socket: &mut Stream, client_id: ClientId) -> Result<Option<AnyCodec>>
+where
+    Stream: AsyncWrite + AsyncRead + Unpin,
+{
+    let io_error = |error| Error::PickCodec { client_id, error };
+
+    let mut line = Vec::new();
+    let read_line = async {
+        while line.len() <= MAX_CODEC_NAME_LEN {
+            match socket.read_u8().await? {
+                b'\n' => return Ok(true),
+                byte => line.push(byte),
+            }
+        }
+        Ok::<_, io::Error>(false)
+    };
+    let codec = match tokio::time::timeout(CODEC_TIMEOUT, read_line).await {
+        Ok(Ok(true)) => std::str::from_utf8(&line)
+            .ok()
+            .and_then(|name| AnyCodec::from_name(name.trim_end_matches('\r'))),
+        Ok(Err(error)) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Ok(Err(error)) => return Err(io_error(error)),
+        Ok(Ok(false)) | Err(_) => None,
+    };
+
+    if codec.is_none() {
+        eprintln!("Client {client_id} did not pick a known codec");
+        let message = format!(
+            "Unknown codec, expected `{}` or `{}`\n",
+            MessagePack::NAME,
+            Json::NAME
+        );
+        socket
+            .write_all(message.as_bytes())
+            .await
+            .map_err(io_error)?;
+    }
+    Ok(codec)
+}
+
+struct ClientConnection<Stream> {
+    client_id: ClientId,
+    connection: Connection<Stream, IncomingRequest, Response, AnyCodec>,
+}
+
+impl<Stream> ClientConnection<Stream>
+where
+    Stream: AsyncWrite + AsyncRead + Unpin,
+{
+    /// Challenges the client to prove it knows `secret`. The client is told when it failed and
+    /// should then be disconnected.
+    async fn authenticate(&mut self, secret: &[u8]) -> Result<bool> {
+        let mut nonce = [0; auth::NONCE_LEN];
+        getrandom::getrandom(&mut nonce).map_err(|error| Error::GenerateNonce {
+            client_id: self.client_id,
+            error,
+        })?;
+        self.write_frame(&Response::unsolicited(ClientCommand::AuthChallenge {
+            nonce: nonce.to_vec(),
+        }))
+        .await?;
+
+        let answer = tokio::time::timeout(AUTHENTICATION_TIMEOUT, self.read_frame()).await;
+        let authenticated = match answer {
+            Ok(Ok(Some(IncomingRequest {
+                command: Some(ServerCommand::Authenticate { signature }),
+                ..
+            }))) => auth::verify_challenge(secret, &nonce, &signature),
+            Ok(Ok(None)) => return Ok(false),
+            Ok(Err(error)) => return Err(error),
+            Ok(Ok(Some(_))) | Err(_) => false,
+        };
+
+        if !authenticated {
+            self.write_frame(&Response::unsolicited(ClientCommand::AuthenticationFailed))
+                .await?;
+        }
+        Ok(authenticated)
+    }
+
+    async fn read_frame(&mut self) -> Result<Option<IncomingRequest>> {
         match self.connection.read_frame().await {
             Ok(value) => Ok(value),
             Err(error) => {
-                let _ = self.write_frame(&ClientCommand::FailedToParseFrame).await;
+                let _ = self
+                    .write_frame(&Response::unsolicited(ClientCommand::FailedToParseFrame))
+                    .await;
                 Err(Error::ReadFrame {
                     client_id: self.client_id,
                     error,
@@ -92,7 +499,7 @@ where
         }
     }
 
-    async fn write_frame(&mut self, frame: &ClientCommand) -> Result<()> {
+    async fn write_frame(&mut self, frame: &Response) -> Result<()> {
         match self.connection.write_frame(frame).await {
             Ok(value) => Ok(value),
             Err(error) => Err(Error::WriteFrame {
@@ -103,8 +510,440 @@ where
     }
 }
 
-impl<Stream, TaskManager> Drop for ClientHandler<Stream, TaskManager> {
-    fn drop(&mut self) {
-        eprintln!("Client {} has disconnected", self.client_id);
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use commands::EventFilter;
+    use test_utils::TestConnection;
+    use tokio::task::JoinHandle;
+
+    const SECRET: &[u8] = b"correct horse battery staple";
+
+    async fn spawn_authenticating_server(
+        task_manager: tasks_manager::MockApi,
+    ) -> (TestConnection, JoinHandle<Result<()>>) {
+        let (client, server) = tokio::io::duplex(4096);
+        let join_handle = tokio::spawn(async move {
+            ClientHandler::process_client(
+                server,
+                task_manager,
+                ClientId::from(0),
+                Some(Role::Admin),
+                Some(SECRET),
+            )
+            .await
+        });
+        (TestConnection::new(client).await, join_handle)
+    }
+
+    async fn read_nonce(client: &mut TestConnection) -> Vec<u8> {
+        match client.read_frame().await.unwrap() {
+            Some(ClientCommand::AuthChallenge { nonce }) => nonce,
+            frame => panic!("Expected a challenge, got {frame:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_authentication() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_client_connected()
+            .once()
+            .returning(|_| Ok(()));
+        let (mut client, server) = spawn_authenticating_server(mock_task_manager).await;
+
+        let nonce = read_nonce(&mut client).await;
+        assert_eq!(nonce.len(), auth::NONCE_LEN);
+        client
+            .write_frame(&ServerCommand::Authenticate {
+                signature: auth::sign_challenge(SECRET, &nonce),
+            })
+            .await
+            .unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame, Some(hello()));
+
+        drop(client);
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_authentication_failure() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager.expect_client_connected().never();
+        let (mut client, server) = spawn_authenticating_server(mock_task_manager).await;
+
+        let nonce = read_nonce(&mut client).await;
+        client
+            .write_frame(&ServerCommand::Authenticate {
+                signature: auth::sign_challenge(b"wrong secret", &nonce),
+            })
+            .await
+            .unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame, Some(ClientCommand::AuthenticationFailed));
+
+        // The server hangs up
+        assert_eq!(client.read_frame().await.unwrap(), None);
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_authentication_skipped() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager.expect_list_tasks().never();
+        let (mut client, server) = spawn_authenticating_server(mock_task_manager).await;
+
+        read_nonce(&mut client).await;
+        client.write_frame(&ServerCommand::ListTasks).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame, Some(ClientCommand::AuthenticationFailed));
+
+        server.await.unwrap().unwrap();
+    }
+
+    fn spawn_raw_server(
+        task_manager: tasks_manager::MockApi,
+    ) -> (tokio::io::DuplexStream, JoinHandle<Result<()>>) {
+        let (client, server) = tokio::io::duplex(4096);
+        let join_handle = tokio::spawn(async move {
+            ClientHandler::process_client(
+                server,
+                task_manager,
+                ClientId::from(0),
+                Some(Role::Admin),
+                None,
+            )
+            .await
+        });
+        (client, join_handle)
+    }
+
+    #[tokio::test]
+    async fn test_json_codec() {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_client_connected()
+            .once()
+            .returning(|_| Ok(()));
+        mock_task_manager
+            .expect_list_tasks()
+            .once()
+            .returning(|| Ok(vec!["nginx".to_string()]));
+        let (client, server) = spawn_raw_server(mock_task_manager);
+        let mut client = BufReader::new(client);
+
+        client.write_all(b"json\r\n").await.unwrap();
+        let mut line = String::new();
+        client.read_line(&mut line).await.unwrap();
+        let greeting: Response = serde_json::from_str(&line).unwrap();
+        assert_eq!(greeting, Response::unsolicited(hello()));
+
+        client
+            .write_all(b"{\"id\": 7, \"command\": \"ListTasks\"}\n")
+            .await
+            .unwrap();
+        line.clear();
+        client.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "{\"id\":7,\"command\":{\"TaskList\":[\"nginx\"]}}\n");
+
+        drop(client);
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unknown_command() {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_client_connected()
+            .once()
+            .returning(|_| Ok(()));
+        mock_task_manager
+            .expect_list_tasks()
+            .once()
+            .returning(|| Ok(vec!["nginx".to_string()]));
+        let (client, server) = spawn_raw_server(mock_task_manager);
+        let mut client = BufReader::new(client);
+
+        client.write_all(b"json\n").await.unwrap();
+        let mut line = String::new();
+        client.read_line(&mut line).await.unwrap();
+
+        // Sent by a newer client
+        client
+            .write_all(b"{\"id\": 3, \"command\": {\"Reboot\": {\"delay\": 5}}}\n")
+            .await
+            .unwrap();
+        line.clear();
+        client.read_line(&mut line).await.unwrap();
+        let response: Response = serde_json::from_str(&line).unwrap();
+        assert_eq!(
+            response,
+            Response::new(
+                3,
+                ClientCommand::error(
+                    ErrorKind::UnsupportedCommand,
+                    "The server does not know this command",
+                    None
+                )
+            )
+        );
+
+        // The connection is still usable
+        client
+            .write_all(b"{\"id\": 4, \"command\": \"ListTasks\"}\n")
+            .await
+            .unwrap();
+        line.clear();
+        client.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "{\"id\":4,\"command\":{\"TaskList\":[\"nginx\"]}}\n");
+
+        drop(client);
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unknown_codec() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager.expect_client_connected().never();
+        let (mut client, server) = spawn_raw_server(mock_task_manager);
+
+        client.write_all(b"xml\n").await.unwrap();
+        let mut answer = String::new();
+        client.read_to_string(&mut answer).await.unwrap();
+        assert_eq!(answer, "Unknown codec, expected `msgpack` or `json`\n");
+
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_permission_denied() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager.expect_stop().never();
+        mock_task_manager
+            .expect_list_tasks()
+            .once()
+            .returning(|| Ok(vec!["nginx".to_string()]));
+
+        let (mut client, server) =
+            test_utils::setup_test_with_role(mock_task_manager, Some(Role::ReadOnly)).await;
+
+        client
+            .write_frame(&ServerCommand::Stop {
+                target: "nginx".to_string(),
+                timeout: None,
+                kill: false,
+            })
+            .await
+            .unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::error(
+                ErrorKind::PermissionDenied,
+                "The command requires the `Operator` role",
+                Some("nginx".to_string())
+            ))
+        );
+
+        // The connection is still usable for the commands the role allows
+        client.write_frame(&ServerCommand::ListTasks).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::TaskList(vec!["nginx".to_string()]))
+        );
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_internal_error() {
+        let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+        drop(sender);
+        let error = tasks_manager::Error::Call(receiver.await.unwrap_err().into());
+        let message = error.to_string();
+
+        let mut sequence = mockall::Sequence::new();
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_list_tasks()
+            .once()
+            .in_sequence(&mut sequence)
+            .return_once(|| Err(error));
+        mock_task_manager
+            .expect_list_tasks()
+            .once()
+            .in_sequence(&mut sequence)
+            .return_once(|| Ok(vec!["nginx".to_string()]));
+
+        let (mut client, server) = test_utils::setup_test(mock_task_manager).await;
+
+        client.write_frame(&ServerCommand::ListTasks).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::error(ErrorKind::Internal, message, None))
+        );
+
+        // The session goes on
+        client.write_frame(&ServerCommand::ListTasks).await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::TaskList(vec!["nginx".to_string()]))
+        );
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_request() {
+        let (_sender, receiver) = tokio::sync::mpsc::channel(10);
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_subscribe()
+            .once()
+            .return_once(|_| Ok(receiver));
+        mock_task_manager.expect_list_tasks().never();
+
+        let (mut client, server) = test_utils::setup_test(mock_task_manager).await;
+
+        let subscribe = ServerCommand::Subscribe {
+            filter: EventFilter::default(),
+        };
+        let id = client.write_frame(&subscribe).await.unwrap();
+        client
+            .write_request(id, &ServerCommand::ListTasks)
+            .await
+            .unwrap();
+        let response = client.read_response().await.unwrap();
+        assert_eq!(
+            response,
+            Some(Response::new(
+                id,
+                ClientCommand::error(
+                    ErrorKind::DuplicateRequest,
+                    format!("Request {id} is still in flight"),
+                    None
+                )
+            ))
+        );
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_streams_not_counted_in_flight() {
+        let senders = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        let streams = std::sync::Arc::clone(&senders);
+        mock_task_manager
+            .expect_subscribe()
+            .times(MAX_REQUESTS_IN_FLIGHT + 1)
+            .returning(move |_| {
+                let (sender, receiver) = tokio::sync::mpsc::channel(10);
+                streams.lock().unwrap().push(sender);
+                Ok(receiver)
+            });
+        mock_task_manager
+            .expect_list_tasks()
+            .once()
+            .return_once(|| Ok(vec!["nginx".to_string()]));
+
+        let (mut client, server) = test_utils::setup_test(mock_task_manager).await;
+
+        for _ in 0..=MAX_REQUESTS_IN_FLIGHT {
+            client
+                .write_frame(&ServerCommand::Subscribe {
+                    filter: EventFilter::default(),
+                })
+                .await
+                .unwrap();
+        }
+        let id = client.write_frame(&ServerCommand::ListTasks).await.unwrap();
+        let response = client.read_response().await.unwrap();
+        assert_eq!(
+            response,
+            Some(Response::new(
+                id,
+                ClientCommand::TaskList(vec!["nginx".to_string()])
+            ))
+        );
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_requests_answered_after_shutdown() {
+        let (_sender, receiver) = tokio::sync::mpsc::channel(10);
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager
+            .expect_subscribe()
+            .once()
+            .return_once(|_| Ok(receiver));
+        mock_task_manager
+            .expect_list_tasks()
+            .once()
+            .return_once(|| Ok(vec!["nginx".to_string()]));
+
+        let (mut client, server) = test_utils::setup_test(mock_task_manager).await;
+
+        let subscribe_id = client
+            .write_frame(&ServerCommand::Subscribe {
+                filter: EventFilter::default(),
+            })
+            .await
+            .unwrap();
+        let list_id = client.write_frame(&ServerCommand::ListTasks).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        // The stream ends with the requests of the client
+        let mut responses = vec![
+            client.read_response().await.unwrap().unwrap(),
+            client.read_response().await.unwrap().unwrap(),
+        ];
+        responses.sort_by_key(|response| response.id);
+        assert_eq!(
+            responses,
+            [
+                Response::new(subscribe_id, ClientCommand::EventStreamEnded),
+                Response::new(list_id, ClientCommand::TaskList(vec!["nginx".to_string()])),
+            ]
+        );
+        assert_eq!(client.read_response().await.unwrap(), None);
+
+        server.check_errors(client).await;
+    }
+
+    #[tokio::test]
+    async fn test_no_role() {
+        let mut mock_task_manager = tasks_manager::MockApi::new();
+        mock_task_manager.expect_subscribe().never();
+
+        let (mut client, server) = test_utils::setup_test_with_role(mock_task_manager, None).await;
+
+        client
+            .write_frame(&ServerCommand::Subscribe {
+                filter: EventFilter::default(),
+            })
+            .await
+            .unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(
+            frame,
+            Some(ClientCommand::error(
+                ErrorKind::PermissionDenied,
+                "The command requires the `ReadOnly` role",
+                None
+            ))
+        );
+
+        server.check_errors(client).await;
     }
 }
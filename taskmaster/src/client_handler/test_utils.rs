@@ -1,12 +1,63 @@
 use crate::{
-    client_handler::{ClientHandler, ClientId},
+    client_handler::{ClientHandler, ClientId, hello},
     tasks_manager,
 };
-use commands::{ClientCommand, ServerCommand};
+use commands::{ClientCommand, Request, RequestId, Response, Role, ServerCommand};
 use connection::Connection;
-use tokio::{io::DuplexStream, task::JoinHandle};
+use tokio::{
+    io::{AsyncWriteExt, DuplexStream},
+    task::JoinHandle,
+};
+
+/// Client numbering its requests from 1.
+pub struct TestConnection {
+    connection: Connection<DuplexStream, Response, Request>,
+    last_id: RequestId,
+}
+
+impl TestConnection {
+    /// Picks the MessagePack codec.
+    pub async fn new(mut stream: DuplexStream) -> Self {
+        stream.write_all(b"msgpack\n").await.unwrap();
+        Self {
+            connection: Connection::new(stream, 4096),
+            last_id: 0,
+        }
+    }
 
-type TestConnection = Connection<DuplexStream, ClientCommand, ServerCommand>;
+    /// Sends `command` in a new request and returns its id.
+    pub async fn write_frame(&mut self, command: &ServerCommand) -> connection::Result<RequestId> {
+        self.last_id += 1;
+        self.write_request(self.last_id, command).await?;
+        Ok(self.last_id)
+    }
+
+    pub async fn write_request(
+        &mut self,
+        id: RequestId,
+        command: &ServerCommand,
+    ) -> connection::Result<()> {
+        let request = Request {
+            id,
+            command: command.clone(),
+        };
+        self.connection.write_frame(&request).await
+    }
+
+    /// Tells the server that no more requests are coming.
+    pub async fn shutdown(&mut self) -> connection::Result<()> {
+        self.connection.shutdown().await
+    }
+
+    /// Reads the next response, whatever request it answers.
+    pub async fn read_frame(&mut self) -> connection::Result<Option<ClientCommand>> {
+        Ok(self.read_response().await?.map(|response| response.command))
+    }
+
+    pub async fn read_response(&mut self) -> connection::Result<Option<Response>> {
+        self.connection.read_frame().await
+    }
+}
 
 pub struct TestServer {
     join_handle: Option<JoinHandle<()>>,
@@ -14,10 +65,10 @@ pub struct TestServer {
 }
 
 impl TestServer {
-    fn new(server: DuplexStream, task_manager: tasks_manager::MockApi) -> Self {
+    fn new(server: DuplexStream, task_manager: tasks_manager::MockApi, role: Option<Role>) -> Self {
         Self {
             join_handle: Some(tokio::spawn(async move {
-                ClientHandler::process_client(server, task_manager, ClientId::from(0))
+                ClientHandler::process_client(server, task_manager, ClientId::from(0), role, None)
                     .await
                     .unwrap();
             })),
@@ -41,14 +92,25 @@ impl Drop for TestServer {
 }
 
 pub async fn setup_test(task_manager: tasks_manager::MockApi) -> (TestConnection, TestServer) {
+    setup_test_with_role(task_manager, Some(Role::Admin)).await
+}
+
+pub async fn setup_test_with_role(
+    mut task_manager: tasks_manager::MockApi,
+    role: Option<Role>,
+) -> (TestConnection, TestServer) {
+    task_manager
+        .expect_client_connected()
+        .once()
+        .returning(|_| Ok(()));
     let (client, server) = tokio::io::duplex(4096);
 
-    let mut client = TestConnection::new(client, 4096);
+    let mut client = TestConnection::new(client).await;
 
-    let server = TestServer::new(server, task_manager);
+    let server = TestServer::new(server, task_manager, role);
 
     let frame = client.read_frame().await.unwrap();
-    assert_eq!(frame, Some(ClientCommand::SuccessfulConnection));
+    assert_eq!(frame, Some(hello()));
 
     (client, server)
 }
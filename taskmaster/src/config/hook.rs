@@ -0,0 +1,54 @@
+use crate::config::program::Command;
+use serde::Deserialize;
+
+/// A change in the state of a process that hooks can be run on.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HookEvent {
+    /// The process was spawned
+    Started,
+    /// The process is ready, according to the `readiness` of its program
+    Running,
+    /// The process exited after it was running
+    Exited,
+    /// The process failed to start and will be retried
+    Backoff,
+    /// The process failed to start and ran out of `startretries`
+    Fatal,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::Started => "started",
+            HookEvent::Running => "running",
+            HookEvent::Exited => "exited",
+            HookEvent::Backoff => "backoff",
+            HookEvent::Fatal => "fatal",
+        }
+    }
+}
+
+/// A command run by the daemon when a process goes through one of `events`.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    /// Every event runs the hook when empty
+    #[serde(default)]
+    pub events: Vec<HookEvent>,
+    pub cmd: Command,
+    /// Seconds after which the hook is killed, along with the processes it started
+    #[serde(default = "default_timeout")]
+    pub timeout: u32,
+}
+
+impl Hook {
+    pub fn runs_on(&self, event: HookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+fn default_timeout() -> u32 {
+    10
+}
@@ -4,14 +4,27 @@ pub use program::Program;
 mod error;
 pub use error::ParseError;
 
+mod hook;
+pub use hook::{Hook, HookEvent};
+
+mod output;
+pub use output::{Facility, Output};
+
+mod pattern;
+pub use pattern::Pattern;
+
+mod server;
+pub use server::{RoleMembers, Roles, ServerConfig, Tls, UnixSocket};
+
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::File;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct Config {
     pub programs: Vec<Program>,
+    pub server: ServerConfig,
 }
 
 #[derive(Deserialize)]
@@ -19,6 +32,11 @@ pub struct Config {
 struct TmpConfig {
     #[serde(with = "::serde_with::rust::maps_duplicate_key_is_error")]
     pub programs: HashMap<String, Program>,
+    /// Hooks run for every program, after the hooks of the program
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+    #[serde(default)]
+    pub server: ServerConfig,
 }
 
 impl Config {
@@ -30,9 +48,11 @@ impl Config {
                 .into_iter()
                 .map(|(name, mut program)| {
                     *program.name_mut() = name;
+                    program.hooks_mut().extend(tmp_config.hooks.iter().cloned());
                     program
                 })
                 .collect(),
+            server: tmp_config.server,
         };
         Ok(config)
     }
@@ -0,0 +1,112 @@
+use crate::config::error::CommandError;
+use crate::config::program::Command;
+use serde::{Deserialize, Deserializer};
+use std::str::FromStr;
+
+const DEFAULT_SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Where the stdout or stderr of a program is written.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone)]
+pub enum Output {
+    /// A file, given by its path
+    File(String),
+    /// RFC5424 datagrams sent to a local syslog daemon, given as `syslog:` for `/dev/log` or
+    /// `syslog:<socket path>`
+    Syslog { socket: String },
+    /// The stdin of a companion process, given as `|command args`
+    Pipe(Command),
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self::File("/dev/null".to_string())
+    }
+}
+
+impl FromStr for Output {
+    type Err = CommandError;
+
+    fn from_str(output: &str) -> Result<Self, Self::Err> {
+        if let Some(command) = output.strip_prefix('|') {
+            return Ok(Self::Pipe(Command::from_str(command)?));
+        }
+
+        Ok(match output.strip_prefix("syslog:") {
+            Some("") => Self::Syslog {
+                socket: DEFAULT_SYSLOG_SOCKET.to_string(),
+            },
+            Some(socket) => Self::Syslog {
+                socket: socket.to_string(),
+            },
+            None => Self::File(output.to_string()),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Output {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let output = String::deserialize(deserializer)?;
+        Output::from_str(&output)
+            .map_err(|err| serde::de::Error::custom(format!("Invalid output `{output}`: {err}")))
+    }
+}
+
+/// Syslog facility of the messages sent to a `syslog:` output.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Facility {
+    Kern,
+    #[default]
+    User,
+    Mail,
+    Daemon,
+    Auth,
+    Syslog,
+    Lpr,
+    News,
+    Uucp,
+    Cron,
+    Authpriv,
+    Ftp,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+impl Facility {
+    /// Numerical code of the facility, as defined by RFC5424.
+    pub fn code(self) -> u8 {
+        match self {
+            Self::Kern => 0,
+            Self::User => 1,
+            Self::Mail => 2,
+            Self::Daemon => 3,
+            Self::Auth => 4,
+            Self::Syslog => 5,
+            Self::Lpr => 6,
+            Self::News => 7,
+            Self::Uucp => 8,
+            Self::Cron => 9,
+            Self::Authpriv => 10,
+            Self::Ftp => 11,
+            Self::Local0 => 16,
+            Self::Local1 => 17,
+            Self::Local2 => 18,
+            Self::Local3 => 19,
+            Self::Local4 => 20,
+            Self::Local5 => 21,
+            Self::Local6 => 22,
+            Self::Local7 => 23,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::ops::Deref;
+
+/// A regular expression read from the config file, matched against program output lines.
+#[derive(Debug, Clone)]
+pub struct Pattern(Regex);
+
+impl Deref for Pattern {
+    type Target = Regex;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern)
+            .map(Pattern)
+            .map_err(|err| serde::de::Error::custom(format!("Invalid regex `{pattern}`: {err}")))
+    }
+}
+
+impl TryFrom<&str> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(pattern: &str) -> Result<Self, Self::Error> {
+        Regex::new(pattern).map(Pattern)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::config::error::CommandError;
+use crate::config::{Facility, Hook, Output, Pattern};
 use derive_getters::Getters;
 use libc::sys::types::Pid;
 use libc::unistd::mode_t;
@@ -7,7 +8,7 @@ use signal::Signal;
 use std::{collections::HashMap, fmt::Display, str::FromStr};
 
 #[cfg_attr(test, derive(PartialEq))]
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, Clone)]
 pub enum AutoRestart {
     #[serde(rename = "true")]
     True,
@@ -18,15 +19,100 @@ pub enum AutoRestart {
     OnFailure,
 }
 
+/// How taskmaster decides that a freshly spawned process is ready, i.e. when it goes from
+/// `Starting` to `Running`.
+///
+/// Except with `starttime`, the process is given `timeout` seconds to become ready, 60 by default.
+/// It is then killed, and counts as a failed start.
 #[cfg_attr(test, derive(PartialEq))]
-#[derive(Debug)]
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Readiness {
+    /// The process is ready once it has been alive for `starttime` seconds.
+    #[default]
+    StartTime,
+    /// The process is ready once a line of its stdout or stderr matches the pattern.
+    Regex {
+        pattern: Pattern,
+        #[serde(default = "default_ready_timeout")]
+        timeout: u32,
+    },
+    /// The process is ready once a TCP connection to the address succeeds.
+    Tcp {
+        address: String,
+        #[serde(default = "default_ready_timeout")]
+        timeout: u32,
+    },
+    /// The process is ready once it sends `READY=1` on the datagram socket given to it through
+    /// the `NOTIFY_SOCKET` environment variable (sd_notify protocol).
+    Notify {
+        #[serde(default = "default_ready_timeout")]
+        timeout: u32,
+    },
+}
+
+impl Readiness {
+    /// Seconds given to the process to become ready, `None` for `starttime` which is a delay
+    /// rather than a condition
+    pub fn timeout(&self) -> Option<u32> {
+        match self {
+            Self::StartTime => None,
+            Self::Regex { timeout, .. } | Self::Tcp { timeout, .. } | Self::Notify { timeout } => {
+                Some(*timeout)
+            }
+        }
+    }
+}
+
+/// What to do when a line of the program output matches the pattern of a trigger.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum TriggerAction {
+    /// Stop the process with its `stopsignal` and start it again, whatever its `autorestart`
+    Restart,
+    /// Send a signal to the process
+    Signal {
+        #[serde(deserialize_with = "deserialize_signal")]
+        signal: Signal,
+    },
+    /// Report the process as unhealthy, it keeps running
+    Unhealthy,
+    /// Send an event to the clients subscribed to the events of the program
+    Event,
+}
+
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Deserialize, Clone)]
+pub struct Trigger {
+    pub pattern: Pattern,
+    #[serde(flatten)]
+    pub action: TriggerAction,
+}
+
+/// How the lines captured from a process are written to its output files.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// The program output, untouched
+    #[default]
+    Raw,
+    /// Each line prefixed with an RFC3339 timestamp, the stream and the instance
+    Timestamped,
+    /// One JSON object per line
+    Json,
+}
+
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone)]
 pub struct Command {
     pub exec: String,
     pub args: Vec<String>,
 }
 
 #[allow(dead_code)] // TODO: remove this
-#[derive(Debug, Getters, Deserialize)]
+#[derive(Debug, Getters, Deserialize, Clone)]
 #[cfg_attr(test, derive(PartialEq))]
 #[serde(deny_unknown_fields)]
 pub struct Program {
@@ -62,6 +148,21 @@ pub struct Program {
     #[serde(rename = "starttime", default)]
     start_time: u32,
 
+    #[serde(default)]
+    readiness: Readiness,
+
+    /// Programs whose processes must all be `Running`, according to their `readiness`, before
+    /// the processes of this one are started by `autostart`
+    #[serde(default)]
+    depends_on: Vec<String>,
+
+    #[serde(default)]
+    triggers: Vec<Trigger>,
+
+    /// The hooks of the program, followed by the global ones
+    #[serde(default)]
+    hooks: Vec<Hook>,
+
     #[serde(
         rename = "stopsignal",
         default = "default_signal",
@@ -69,14 +170,50 @@ pub struct Program {
     )]
     stop_signal: Signal,
 
-    #[serde(rename = "stoptime", default)]
+    /// Seconds given to the process to exit after `stopsignal` before it is killed, 0 to kill it
+    /// right away
+    #[serde(rename = "stoptime", default = "default_stop_time")]
     stop_time: u32,
 
-    #[serde(default = "default_output")]
-    stdout: String,
+    #[serde(default)]
+    stdout: Output,
+
+    #[serde(default)]
+    stderr: Output,
+
+    #[serde(default)]
+    redirect_stderr: bool,
+
+    #[serde(rename = "stdout_maxbytes", default)]
+    stdout_max_bytes: u64,
+
+    #[serde(default)]
+    stdout_backups: u32,
+
+    #[serde(rename = "stderr_maxbytes", default)]
+    stderr_max_bytes: u64,
+
+    #[serde(default)]
+    stderr_backups: u32,
+
+    #[serde(default)]
+    append: bool,
+
+    #[serde(default)]
+    syslog_facility: Facility,
+
+    /// APP-NAME of the messages sent to a `syslog:` output, the program name by default
+    #[serde(default, deserialize_with = "deserialize_syslog_tag")]
+    syslog_tag: Option<String>,
 
-    #[serde(default = "default_output")]
-    stderr: String,
+    #[serde(default)]
+    log_format: LogFormat,
+
+    #[serde(default = "default_log_buffer_lines")]
+    log_buffer_lines: usize,
+
+    #[serde(default = "default_max_line_length")]
+    max_line_length: usize,
 
     #[serde(rename = "clearenv", default)]
     clear_env: bool,
@@ -89,14 +226,35 @@ fn deserialize_signal<'de, D>(deserializer: D) -> Result<Signal, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let mut signal_str = String::deserialize(deserializer)
+    let signal_str = String::deserialize(deserializer)
         .map_err(|err| serde::de::Error::custom(format!("Failed to parse signal: {err}")))?;
-    if !signal_str.starts_with("SIG") {
-        signal_str = format!("SIG{signal_str}");
+    parse_signal(&signal_str)
+        .map_err(|err| de::Error::custom(format!("Failed to convert signal from string: {err}")))
+}
+
+/// Accepts a tag that fits in the RFC5424 APP-NAME field: 1 to 48 printable ASCII characters,
+/// without spaces.
+fn deserialize_syslog_tag<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let tag = String::deserialize(deserializer)?;
+    if tag.is_empty() || tag.len() > 48 || !tag.bytes().all(|byte| byte.is_ascii_graphic()) {
+        return Err(de::Error::custom(format!(
+            "Invalid syslog tag `{tag}`: expected 1 to 48 printable ASCII characters without spaces"
+        )));
     }
-    let signal: Signal = Signal::from_str(signal_str.as_str())
-        .map_err(|err| de::Error::custom(format!("Failed to convert signal from string: {err}")))?;
-    Ok(signal)
+    Ok(Some(tag))
+}
+
+/// Parses a signal name, with or without its `SIG` prefix (`HUP` or `SIGHUP`).
+pub fn parse_signal(name: &str) -> Result<Signal, String> {
+    let signal = if name.starts_with("SIG") {
+        Signal::from_str(name)
+    } else {
+        Signal::from_str(&format!("SIG{name}"))
+    };
+    signal.map_err(|err| err.to_string())
 }
 
 fn deserialize_umask<'de, D>(deserializer: D) -> Result<mode_t, D::Error>
@@ -117,14 +275,26 @@ where
     }
 }
 
-fn default_output() -> String {
-    "/dev/null".to_string()
+fn default_log_buffer_lines() -> usize {
+    200
+}
+
+fn default_max_line_length() -> usize {
+    64 * 1024
 }
 
 fn default_signal() -> Signal {
     Signal::SIGINT
 }
 
+fn default_ready_timeout() -> u32 {
+    60
+}
+
+fn default_stop_time() -> u32 {
+    10
+}
+
 fn default_num_procs() -> u32 {
     1
 }
@@ -161,6 +331,10 @@ impl Program {
     pub(super) fn name_mut(&mut self) -> &mut String {
         &mut self.name
     }
+
+    pub(super) fn hooks_mut(&mut self) -> &mut Vec<Hook> {
+        &mut self.hooks
+    }
 }
 
 impl<'de> Deserialize<'de> for Command {
@@ -190,8 +364,12 @@ impl FromStr for Command {
 
 #[cfg(test)]
 mod tests {
-    use crate::config::program::{AutoRestart, CommandError};
-    use crate::config::{Config, program::Command, program::Program};
+    use crate::config::program::{
+        AutoRestart, CommandError, LogFormat, Readiness, Trigger, TriggerAction, parse_signal,
+    };
+    use crate::config::{
+        Config, Facility, Hook, HookEvent, Output, Pattern, program::Command, program::Program,
+    };
     use libc::unistd::mode_t;
     use signal::Signal;
     use std::collections::HashMap;
@@ -223,11 +401,26 @@ mod tests {
         pub auto_start: bool,
         pub start_retries: u32,
         pub start_time: u32,
+        pub readiness: Readiness,
+        pub depends_on: Vec<String>,
+        pub triggers: Vec<Trigger>,
+        pub hooks: Vec<Hook>,
         pub stop_time: u32,
         pub stop_signal: Signal,
         pub clear_env: bool,
-        pub stdout: String,
-        pub stderr: String,
+        pub stdout: Output,
+        pub stderr: Output,
+        pub redirect_stderr: bool,
+        pub stdout_max_bytes: u64,
+        pub stdout_backups: u32,
+        pub stderr_max_bytes: u64,
+        pub stderr_backups: u32,
+        pub append: bool,
+        pub syslog_facility: Facility,
+        pub syslog_tag: Option<String>,
+        pub log_format: LogFormat,
+        pub log_buffer_lines: usize,
+        pub max_line_length: usize,
         pub env: HashMap<String, String>,
     }
 
@@ -244,11 +437,26 @@ mod tests {
                 auto_start: false,
                 start_retries: 0,
                 start_time: 0,
-                stop_time: 0,
+                readiness: Readiness::StartTime,
+                depends_on: Vec::new(),
+                triggers: Vec::new(),
+                hooks: Vec::new(),
+                stop_time: 10,
                 stop_signal: Signal::SIGINT,
                 clear_env: false,
-                stdout: "/dev/null".to_string(),
-                stderr: "/dev/null".to_string(),
+                stdout: Output::default(),
+                stderr: Output::default(),
+                redirect_stderr: false,
+                stdout_max_bytes: 0,
+                stdout_backups: 0,
+                stderr_max_bytes: 0,
+                stderr_backups: 0,
+                append: false,
+                syslog_facility: Facility::User,
+                syslog_tag: None,
+                log_format: LogFormat::Raw,
+                log_buffer_lines: 200,
+                max_line_length: 64 * 1024,
                 env: HashMap::new(),
             })
         }
@@ -271,11 +479,26 @@ mod tests {
                 auto_start: self.auto_start,
                 start_retries: self.start_retries,
                 start_time: self.start_time,
+                readiness: self.readiness,
+                depends_on: self.depends_on,
+                triggers: self.triggers,
+                hooks: self.hooks,
                 stop_time: self.stop_time,
                 stop_signal: self.stop_signal,
                 clear_env: self.clear_env,
                 stdout: self.stdout,
                 stderr: self.stderr,
+                redirect_stderr: self.redirect_stderr,
+                stdout_max_bytes: self.stdout_max_bytes,
+                stdout_backups: self.stdout_backups,
+                stderr_max_bytes: self.stderr_max_bytes,
+                stderr_backups: self.stderr_backups,
+                append: self.append,
+                syslog_facility: self.syslog_facility,
+                syslog_tag: self.syslog_tag,
+                log_format: self.log_format,
+                log_buffer_lines: self.log_buffer_lines,
+                max_line_length: self.max_line_length,
             };
 
             Ok(program)
@@ -285,6 +508,7 @@ mod tests {
     fn assert_config_parses_to(yaml_content: &str, expected_program: Program) {
         let expected_config = Config {
             programs: vec![expected_program],
+            server: Default::default(),
         };
 
         let config_reader = Cursor::new(yaml_content);
@@ -439,6 +663,19 @@ mod tests {
         assert_config_parses_to(&yaml_content, program);
     }
 
+    #[test]
+    fn parsing_with_depends_on() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.depends_on = vec!["database".to_string(), "cache".to_string()];
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            depends_on: [database, cache]"#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
     #[test]
     fn parsing_with_start_retries() {
         let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
@@ -465,6 +702,197 @@ mod tests {
         assert_config_parses_to(&yaml_content, program);
     }
 
+    #[test]
+    fn parsing_with_readiness_regex() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.readiness = Readiness::Regex {
+            pattern: Pattern::try_from("listening on port \\d+").expect("Invalid regex"),
+            timeout: 60,
+        };
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            readiness:
+                type: regex
+                pattern: "listening on port \\d+""#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_readiness_tcp() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.readiness = Readiness::Tcp {
+            address: "127.0.0.1:8080".to_string(),
+            timeout: 5,
+        };
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            readiness:
+                type: tcp
+                address: "127.0.0.1:8080"
+                timeout: 5"#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_readiness_notify() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.readiness = Readiness::Notify { timeout: 60 };
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            readiness:
+                type: notify"#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_invalid_readiness_regex() {
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            readiness:
+                type: regex
+                pattern: "unclosed (group""#,
+        );
+        assert_config_parsing_error(&yaml_content);
+    }
+
+    #[test]
+    fn parsing_with_triggers() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.triggers = vec![
+            Trigger {
+                pattern: Pattern::try_from("connection pool exhausted").unwrap(),
+                action: TriggerAction::Restart,
+            },
+            Trigger {
+                pattern: Pattern::try_from("^reload$").unwrap(),
+                action: TriggerAction::Signal {
+                    signal: Signal::SIGHUP,
+                },
+            },
+            Trigger {
+                pattern: Pattern::try_from("slow query").unwrap(),
+                action: TriggerAction::Unhealthy,
+            },
+            Trigger {
+                pattern: Pattern::try_from("deploy done").unwrap(),
+                action: TriggerAction::Event,
+            },
+        ];
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            triggers:
+              - pattern: "connection pool exhausted"
+                action: restart
+              - pattern: "^reload$"
+                action: signal
+                signal: HUP
+              - pattern: "slow query"
+                action: unhealthy
+              - pattern: "deploy done"
+                action: event"#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_invalid_trigger_action() {
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            triggers:
+              - pattern: "oops"
+                action: explode"#,
+        );
+        assert_config_parsing_error(&yaml_content);
+    }
+
+    #[test]
+    fn parsing_with_hooks() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.hooks = vec![
+            Hook {
+                events: vec![HookEvent::Exited, HookEvent::Fatal],
+                cmd: Command::from_str("notify-send crashed").unwrap(),
+                timeout: 5,
+            },
+            Hook {
+                events: vec![],
+                cmd: Command::from_str("logger").unwrap(),
+                timeout: 10,
+            },
+        ];
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            hooks:
+              - events: [exited, fatal]
+                cmd: "notify-send crashed"
+                timeout: 5
+              - cmd: "logger""#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_global_hooks() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.hooks = vec![
+            Hook {
+                events: vec![HookEvent::Running],
+                cmd: Command::from_str("program-hook").unwrap(),
+                timeout: 10,
+            },
+            Hook {
+                events: vec![HookEvent::Backoff],
+                cmd: Command::from_str("global-hook").unwrap(),
+                timeout: 10,
+            },
+        ];
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = r#"hooks:
+          - events: [backoff]
+            cmd: "global-hook"
+programs:
+    taskmaster_test_program:
+        cmd: "echo test"
+        hooks:
+          - events: [running]
+            cmd: "program-hook""#;
+        assert_config_parses_to(yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_invalid_hook_event() {
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            hooks:
+              - events: [exploded]
+                cmd: "logger""#,
+        );
+        assert_config_parsing_error(&yaml_content);
+    }
+
+    #[test]
+    fn test_parse_signal() {
+        assert_eq!(parse_signal("HUP"), Ok(Signal::SIGHUP));
+        assert_eq!(parse_signal("SIGUSR1"), Ok(Signal::SIGUSR1));
+        assert!(parse_signal("NOPE").is_err());
+    }
+
     #[test]
     fn parsing_with_stop_time() {
         let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
@@ -533,7 +961,7 @@ mod tests {
     #[test]
     fn parsing_with_stdout() {
         let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
-        builder.stdout = "/var/log/stdout.log".to_string();
+        builder.stdout = Output::File("/var/log/stdout.log".to_string());
         let program = builder.build().expect("Failed to build program");
         let yaml_content = yaml_with_fields(
             "echo test",
@@ -546,7 +974,7 @@ mod tests {
     #[test]
     fn parsing_with_stderr() {
         let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
-        builder.stderr = "/var/log/stderr.log".to_string();
+        builder.stderr = Output::File("/var/log/stderr.log".to_string());
         let program = builder.build().expect("Failed to build program");
         let yaml_content = yaml_with_fields(
             "echo test",
@@ -556,6 +984,152 @@ mod tests {
         assert_config_parses_to(&yaml_content, program);
     }
 
+    #[test]
+    fn parsing_with_syslog() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.stdout = Output::Syslog {
+            socket: "/dev/log".to_string(),
+        };
+        builder.stderr = Output::Syslog {
+            socket: "/run/taskmaster/log.sock".to_string(),
+        };
+        builder.syslog_facility = Facility::Local3;
+        builder.syslog_tag = Some("web".to_string());
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            stdout: "syslog:"
+            stderr: "syslog:/run/taskmaster/log.sock"
+            syslog_facility: local3
+            syslog_tag: web"#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_invalid_syslog_tag() {
+        for tag in ["\"web server\"", "\"\"", &"a".repeat(49)] {
+            let yaml_content = yaml_with_fields(
+                "echo test",
+                &format!(
+                    r#"
+            syslog_tag: {tag}"#
+                ),
+            );
+            assert_config_parsing_error(&yaml_content);
+        }
+    }
+
+    #[test]
+    fn parsing_with_pipe() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.stdout = Output::Pipe(Command {
+            exec: "logger".to_string(),
+            args: vec!["-t".to_string(), "web app".to_string()],
+        });
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            stdout: "|logger -t 'web app'""#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_empty_pipe() {
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            stdout: "| ""#,
+        );
+        assert_config_parsing_error(&yaml_content);
+    }
+
+    #[test]
+    fn parsing_with_redirect_stderr() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.redirect_stderr = true;
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            redirect_stderr: true"#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_log_rotation() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.stdout_max_bytes = 1024;
+        builder.stdout_backups = 3;
+        builder.stderr_max_bytes = 2048;
+        builder.stderr_backups = 1;
+        builder.append = true;
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            stdout_maxbytes: 1024
+            stdout_backups: 3
+            stderr_maxbytes: 2048
+            stderr_backups: 1
+            append: true"#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_log_format() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.log_format = LogFormat::Json;
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            log_format: json"#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_log_buffer_lines() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.log_buffer_lines = 1000;
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            log_buffer_lines: 1000"#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_max_line_length() {
+        let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
+        builder.max_line_length = 4096;
+        let program = builder.build().expect("Failed to build program");
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            max_line_length: 4096"#,
+        );
+        assert_config_parses_to(&yaml_content, program);
+    }
+
+    #[test]
+    fn parsing_with_invalid_log_format() {
+        let yaml_content = yaml_with_fields(
+            "echo test",
+            r#"
+            log_format: xml"#,
+        );
+        assert_config_parsing_error(&yaml_content);
+    }
+
     #[test]
     fn parsing_with_env() {
         let mut builder = TestProgramBuilder::new("echo test").expect("Failed to create builder");
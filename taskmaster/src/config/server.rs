@@ -0,0 +1,282 @@
+use libc::unistd::mode_t;
+use serde::{Deserialize, Deserializer};
+use std::path::PathBuf;
+
+/// How clients reach the daemon.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Listen on `<address>:<port>`, the port being given on the command line
+    #[serde(default = "default_tcp")]
+    pub tcp: bool,
+    /// Host name or IP address the tcp listener binds
+    #[serde(default = "default_address")]
+    pub address: String,
+    #[serde(default)]
+    pub unix_socket: Option<UnixSocket>,
+    /// Makes the tcp clients prove they know a shared secret before sending any command
+    #[serde(default)]
+    pub auth: Option<Auth>,
+    /// Encrypts the tcp connections
+    #[serde(default)]
+    pub tls: Option<Tls>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            tcp: default_tcp(),
+            address: default_address(),
+            unix_socket: None,
+            auth: None,
+            tls: None,
+        }
+    }
+}
+
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Auth {
+    /// File holding the secret shared with the clients, read once at startup
+    pub secret_file: PathBuf,
+}
+
+/// PEM files used by the tcp listener.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Tls {
+    /// Certificate chain of the server, leaf first
+    pub cert_file: PathBuf,
+    pub key_file: PathBuf,
+    /// Clients must present a certificate signed by one of these authorities when set
+    #[serde(default)]
+    pub client_ca_file: Option<PathBuf>,
+}
+
+/// A unix domain socket listener, whose file permissions restrict which local users can control
+/// the daemon.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct UnixSocket {
+    pub path: PathBuf,
+    /// Permissions of the socket file, as an octal string
+    #[serde(default = "default_mode", deserialize_with = "deserialize_mode")]
+    pub mode: mode_t,
+    /// `user` or `user:group`, as names or numeric ids. The socket keeps the owner of the daemon
+    /// when unset.
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Roles given to the clients, from the credentials of their process. Every client is an
+    /// admin when unset.
+    #[serde(default)]
+    pub roles: Option<Roles>,
+}
+
+/// Who gets each role. A client gets the highest role matching its user or one of its groups, and
+/// cannot send any command when none matches.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Roles {
+    #[serde(default)]
+    pub read_only: RoleMembers,
+    #[serde(default)]
+    pub operator: RoleMembers,
+    #[serde(default)]
+    pub admin: RoleMembers,
+}
+
+/// Users and groups, as names or numeric ids.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RoleMembers {
+    #[serde(default)]
+    pub users: Vec<String>,
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+fn deserialize_mode<'de, D>(deserializer: D) -> Result<mode_t, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mode_str = String::deserialize(deserializer)?;
+    let mode = mode_t::from_str_radix(&mode_str, 8).map_err(|err| {
+        serde::de::Error::custom(format!("Failed to parse socket mode '{mode_str}': {err}"))
+    })?;
+    if mode > 0o777 {
+        Err(serde::de::Error::custom(
+            "socket mode is greater than 0o777 (max value accepted)",
+        ))
+    } else {
+        Ok(mode)
+    }
+}
+
+fn default_tcp() -> bool {
+    true
+}
+
+fn default_address() -> String {
+    "localhost".to_string()
+}
+
+fn default_mode() -> mode_t {
+    0o600
+}
+
+#[cfg(test)]
+mod test {
+    use super::Auth;
+    use crate::config::{Config, RoleMembers, Roles, ServerConfig, Tls, UnixSocket};
+    use std::io::Cursor;
+
+    fn parse_server(yaml_content: &str) -> Result<ServerConfig, serde_yaml::Error> {
+        Config::from_reader(Cursor::new(yaml_content)).map(|config| config.server)
+    }
+
+    #[test]
+    fn parsing_without_server() {
+        let server = parse_server("programs: {}").unwrap();
+        assert_eq!(server, ServerConfig::default());
+        assert!(server.tcp);
+    }
+
+    #[test]
+    fn parsing_with_unix_socket() {
+        let server = parse_server(
+            r#"programs: {}
+server:
+    tcp: false
+    unix_socket:
+        path: /run/taskmaster.sock
+        mode: "660"
+        owner: "root:taskmaster""#,
+        )
+        .unwrap();
+        assert_eq!(
+            server,
+            ServerConfig {
+                tcp: false,
+                address: "localhost".to_string(),
+                unix_socket: Some(UnixSocket {
+                    path: "/run/taskmaster.sock".into(),
+                    mode: 0o660,
+                    owner: Some("root:taskmaster".to_string()),
+                    roles: None,
+                }),
+                auth: None,
+                tls: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parsing_with_address() {
+        let server = parse_server(
+            r#"programs: {}
+server:
+    address: 0.0.0.0"#,
+        )
+        .unwrap();
+        assert!(server.tcp);
+        assert_eq!(server.address, "0.0.0.0");
+    }
+
+    #[test]
+    fn parsing_with_default_socket_mode() {
+        let server = parse_server(
+            r#"programs: {}
+server:
+    unix_socket:
+        path: /run/taskmaster.sock"#,
+        )
+        .unwrap();
+        assert!(server.tcp);
+        assert_eq!(server.unix_socket.unwrap().mode, 0o600);
+    }
+
+    #[test]
+    fn parsing_with_roles() {
+        let server = parse_server(
+            r#"programs: {}
+server:
+    unix_socket:
+        path: /run/taskmaster.sock
+        roles:
+            admin:
+                users: [root]
+            operator:
+                users: ["1000"]
+                groups: [ops]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            server.unix_socket.unwrap().roles,
+            Some(Roles {
+                read_only: RoleMembers::default(),
+                operator: RoleMembers {
+                    users: vec!["1000".to_string()],
+                    groups: vec!["ops".to_string()],
+                },
+                admin: RoleMembers {
+                    users: vec!["root".to_string()],
+                    groups: vec![],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn parsing_with_auth() {
+        let server = parse_server(
+            r#"programs: {}
+server:
+    auth:
+        secret_file: /etc/taskmaster/secret"#,
+        )
+        .unwrap();
+        assert_eq!(
+            server.auth,
+            Some(Auth {
+                secret_file: "/etc/taskmaster/secret".into()
+            })
+        );
+    }
+
+    #[test]
+    fn parsing_with_tls() {
+        let server = parse_server(
+            r#"programs: {}
+server:
+    tls:
+        cert_file: /etc/taskmaster/cert.pem
+        key_file: /etc/taskmaster/key.pem
+        client_ca_file: /etc/taskmaster/clients.pem"#,
+        )
+        .unwrap();
+        assert_eq!(
+            server.tls,
+            Some(Tls {
+                cert_file: "/etc/taskmaster/cert.pem".into(),
+                key_file: "/etc/taskmaster/key.pem".into(),
+                client_ca_file: Some("/etc/taskmaster/clients.pem".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn parsing_with_invalid_socket_mode() {
+        for mode in ["\"1777\"", "\"rw\""] {
+            let yaml = format!(
+                "programs: {{}}\nserver:\n    unix_socket:\n        path: /tmp/tm.sock\n        mode: {mode}"
+            );
+            assert!(parse_server(&yaml).is_err());
+        }
+    }
+}
@@ -23,13 +23,13 @@ fn main() {
 fn entrypoint() -> Result<()> {
     let Args { port } = parse_args(std::env::args().nth(1))?;
 
-    let tasks = get_tasks_from_config("taskmaster.yaml");
+    let config = get_config("taskmaster.yaml");
 
     if !cfg!(debug_assertions) {
         daemonize()?
     }
 
-    start_server(port, tasks)
+    start_server(port, config)
 }
 
 fn parse_args(port: Option<String>) -> Result<Args> {
@@ -43,6 +43,35 @@ fn parse_args(port: Option<String>) -> Result<Args> {
     Ok(Args { port })
 }
 
+fn get_config(config_file: &str) -> Config {
+    Config::parse(config_file).unwrap_or_else(|err| {
+        eprintln!("Warning {err}");
+        Config::default()
+    })
+}
+
+fn daemonize() -> Result<()> {
+    unsafe {
+        daemonize::Daemonize::new()
+            .stdout("./server_output")
+            .stderr("./server_output")
+            .start()?
+    }
+    Ok(())
+}
+
+fn start_server(port: i32, config: Config) -> Result<()> {
+    tokio::runtime::Runtime::new()
+        .expect("Failed to init tokio runtime")
+        .block_on(async {
+            Server::new(config.programs, port, &config.server)
+                .await?
+                .run()
+                .await;
+            Result::<()>::Ok(())
+        })
+}
+
 #[cfg(test)]
 mod taskmaster {
     use super::*;
@@ -67,35 +96,3 @@ mod taskmaster {
         assert_eq!(input, "hey");
     }
 }
-
-fn get_tasks_from_config(config_file: &str) -> Vec<Program> {
-    match Config::parse(config_file) {
-        Ok(config) => config.programs,
-        Err(err) => {
-            eprintln!("Warning {err}");
-            Vec::new()
-        }
-    }
-}
-
-fn daemonize() -> Result<()> {
-    unsafe {
-        daemonize::Daemonize::new()
-            .stdout("./server_output")
-            .stderr("./server_output")
-            .start()?
-    }
-    Ok(())
-}
-
-fn start_server(port: i32, tasks: Vec<Program>) -> Result<()> {
-    tokio::runtime::Runtime::new()
-        .expect("Failed to init tokio runtime")
-        .block_on(async {
-            Server::new(tasks, format!("localhost:{port}"))
-                .await?
-                .run()
-                .await;
-            Result::<()>::Ok(())
-        })
-}
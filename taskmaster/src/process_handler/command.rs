@@ -1,5 +1,12 @@
 use crate::Program;
-use tokio::process::Command;
+#[cfg(target_os = "linux")]
+use std::os::fd::{FromRawFd, OwnedFd};
+#[cfg(target_os = "linux")]
+use tokio::io::unix::AsyncFd;
+use tokio::{
+    io,
+    process::{Child, Command},
+};
 
 pub(super) fn create_command(config: &Program) -> Command {
     let mut command = Command::new(config.cmd.exec.clone());
@@ -7,6 +14,9 @@ pub(super) fn create_command(config: &Program) -> Command {
         command.arg(arg);
     }
 
+    // The process leads its own process group, so that signals can be sent to its children too
+    command.process_group(0);
+
     if *config.clear_env() {
         command.env_clear();
     }
@@ -16,3 +26,20 @@ pub(super) fn create_command(config: &Program) -> Command {
 
     command
 }
+
+/// Waits for the child to exit. It is left unreaped where pidfds are available, so that its pid,
+/// which cannot be reused until then, can still be signaled safely. It is reaped otherwise.
+pub(super) async fn exited(child: &mut Child) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    if let Some(pid) = child.id() {
+        let pidfd = unsafe { libc::pidfd::pidfd_open(pid as i32, 0) };
+        if pidfd >= 0 {
+            // Readable once the process exited, which does not reap it
+            let pidfd = AsyncFd::new(unsafe { OwnedFd::from_raw_fd(pidfd) })?;
+            drop(pidfd.readable().await?);
+            return Ok(());
+        }
+    }
+
+    child.wait().await.map(drop)
+}
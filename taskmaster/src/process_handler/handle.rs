@@ -1,5 +1,14 @@
-use crate::process_handler::routine::{KillCommandSender, LogReceiver, StatusReceiver};
+use crate::process_handler::hub::{Subscriber, WeakHub, WeakLosslessHub};
+use crate::process_handler::routine::{
+    KillCommandSender, LogReceiver, OutputFiles, StatusReceiver,
+};
+use crate::process_handler::trigger::TriggerEvent;
+use crate::process_handler::{Log, LogBuffer, Status};
 use derive_getters::Getters;
+use libc::signal::kill;
+use signal::Signal;
+use std::io;
+use tokio::sync::watch;
 use tokio::task::JoinHandle as TokioJoinHandle;
 
 type JoinHandle = TokioJoinHandle<()>;
@@ -7,25 +16,71 @@ type JoinHandle = TokioJoinHandle<()>;
 #[derive(Getters)]
 #[allow(dead_code)] //TODO: Remove that
 pub struct Handle {
+    pub instance: u32,
     pub join_handle: JoinHandle,
     pub status_receiver: StatusReceiver,
     pub log_receiver: LogReceiver,
     pub kill_command_sender: KillCommandSender,
+    pub output_files: OutputFiles,
+    pub log_buffer: LogBuffer,
+    pub status_hub: WeakLosslessHub<Status>,
+    pub log_hub: WeakHub<Log>,
+    pub event_hub: WeakHub<TriggerEvent>,
+    pub pid: watch::Receiver<Option<u32>>,
+    /// Whether the process is `Running`, see `Handle::wait_ready`
+    pub ready: watch::Receiver<bool>,
+    /// Set once the process was asked to stop for good, the handle is kept until it exited
+    pub stopping: bool,
 }
 
 #[allow(dead_code)] //TODO: Remove that
 impl Handle {
-    pub(super) fn new(
-        join_handle: tokio::task::JoinHandle<()>,
-        status_receiver: StatusReceiver,
-        log_receiver: LogReceiver,
-        kill_command_sender: KillCommandSender,
-    ) -> Self {
-        Self {
-            join_handle,
-            status_receiver,
-            log_receiver,
-            kill_command_sender,
-        }
+    /// Pid of the process, `None` when it is not alive.
+    pub fn current_pid(&self) -> Option<u32> {
+        *self.pid.borrow()
+    }
+
+    /// Sends `signal` to the process, or to its process group. Returns `None` when the process is
+    /// not alive. The routine cannot reap the process meanwhile, since it clears the pid first.
+    pub fn signal(&self, signal: Signal, process_group: bool) -> Option<io::Result<()>> {
+        let pid = self.pid.borrow();
+        let pid = (*pid)? as i32;
+        // Every process leads its own process group
+        let pid = if process_group { -pid } else { pid };
+        Some(match unsafe { kill(pid, signal as i32) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        })
+    }
+
+    /// Resolves once the process is `Running`, right away if it already is. Returns `false` if
+    /// the routine ended first.
+    pub fn wait_ready(&self) -> impl Future<Output = bool> + use<> {
+        let mut ready = self.ready.clone();
+        async move { ready.wait_for(|ready| *ready).await.is_ok() }
+    }
+
+    /// Whether the routine is over, and its process gone. The routine drops its end of the kill
+    /// commands on its way out.
+    pub fn is_over(&self) -> bool {
+        self.kill_command_sender.is_closed()
+    }
+
+    /// Subscribes to the statuses sent from now on, for as long as the process is supervised.
+    /// Returns `None` if the routine is over.
+    pub fn subscribe_statuses(&self) -> Option<StatusReceiver> {
+        self.status_hub.subscribe()
+    }
+
+    /// Subscribes to the logs captured from now on, for as long as the process is supervised.
+    /// Returns `None` if the routine is over.
+    pub fn subscribe_logs(&self) -> Option<LogReceiver> {
+        self.log_hub.subscribe()
+    }
+
+    /// Subscribes to the events sent by the `event` triggers from now on, for as long as the
+    /// process is supervised. Returns `None` if the routine is over.
+    pub fn subscribe_events(&self) -> Option<Subscriber<TriggerEvent>> {
+        self.event_hub.subscribe()
     }
 }
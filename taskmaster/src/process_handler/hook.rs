@@ -0,0 +1,194 @@
+use super::command;
+use crate::config::{Hook, HookEvent};
+use chrono::{SecondsFormat, Utc};
+use libc::signal::kill;
+use serde::Serialize;
+use signal::Signal;
+use std::{process::Stdio, sync::Arc, time::Duration};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+/// What happened to a process, given to its hooks through environment variables and as JSON on
+/// their stdin.
+#[derive(Debug, Default, Serialize)]
+pub(super) struct HookDetails {
+    pub pid: Option<u32>,
+    pub exit_code: Option<i32>,
+    /// The signal that killed the process
+    pub signal: Option<i32>,
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    timestamp: String,
+    event: &'static str,
+    program: &'a str,
+    instance: u32,
+    #[serde(flatten)]
+    details: &'a HookDetails,
+}
+
+/// Runs the hooks of a process instance in the background, so that a slow hook never delays the
+/// routine supervising the process.
+#[derive(Clone)]
+pub(super) struct HookRunner {
+    hooks: Arc<[Hook]>,
+    program_name: String,
+    instance: u32,
+}
+
+impl HookRunner {
+    pub(super) fn new(hooks: &[Hook], program_name: &str, instance: u32) -> Self {
+        Self {
+            hooks: hooks.into(),
+            program_name: program_name.to_string(),
+            instance,
+        }
+    }
+
+    /// Spawns the hooks that run on `event`, without waiting for them.
+    pub(super) fn run(&self, event: HookEvent, details: HookDetails) {
+        if !self.hooks.iter().any(|hook| hook.runs_on(event)) {
+            return;
+        }
+
+        let payload = serde_json::to_vec(&Payload {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            event: event.as_str(),
+            program: &self.program_name,
+            instance: self.instance,
+            details: &details,
+        })
+        .expect("hook payload is always serializable");
+        let payload: Arc<[u8]> = payload.into();
+
+        for hook in self.hooks.iter().filter(|hook| hook.runs_on(event)) {
+            let mut command = self.command(hook, event, &details);
+            let timeout = Duration::from_secs(hook.timeout as u64);
+            let payload = Arc::clone(&payload);
+            let program_name = self.program_name.clone();
+            let exec = hook.cmd.exec.clone();
+            tokio::spawn(async move {
+                if let Err(err) = Self::execute(&mut command, &payload, timeout).await {
+                    eprintln!(
+                        "Taskmaster error: {program_name}: {} hook `{exec}` failed: {err}",
+                        event.as_str()
+                    );
+                }
+            });
+        }
+    }
+
+    fn command(&self, hook: &Hook, event: HookEvent, details: &HookDetails) -> Command {
+        let mut command = Command::new(&hook.cmd.exec);
+        command
+            .args(&hook.cmd.args)
+            .env("TASKMASTER_EVENT", event.as_str())
+            .env("TASKMASTER_PROGRAM", &self.program_name)
+            .env("TASKMASTER_INSTANCE", self.instance.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            // The hook leads its own process group, so that its children can be killed with it
+            .process_group(0)
+            .kill_on_drop(true);
+        if let Some(pid) = details.pid {
+            command.env("TASKMASTER_PID", pid.to_string());
+        }
+        if let Some(exit_code) = details.exit_code {
+            command.env("TASKMASTER_EXIT_CODE", exit_code.to_string());
+        }
+        if let Some(signal) = details.signal {
+            command.env("TASKMASTER_SIGNAL", signal.to_string());
+        }
+        command
+    }
+
+    /// Runs the hook to completion, killing it once `timeout` is elapsed. Whatever it leaves
+    /// running in its process group is killed once it exits or times out.
+    async fn execute(
+        command: &mut Command,
+        payload: &[u8],
+        timeout: Duration,
+    ) -> Result<(), String> {
+        let mut child = command.spawn().map_err(|err| err.to_string())?;
+        let mut stdin = child.stdin.take();
+
+        let exited = tokio::time::timeout(timeout, async {
+            if let Some(stdin) = stdin.as_mut() {
+                // The hook does not have to read the payload
+                let _ = stdin.write_all(payload).await;
+            }
+            drop(stdin);
+            command::exited(&mut child).await
+        })
+        .await;
+
+        // Only known while the hook is not reaped, so its pid cannot have been reused
+        if let Some(pid) = child.id() {
+            unsafe { kill(-(pid as i32), Signal::SIGKILL as i32) };
+        }
+        let status = child.wait().await;
+
+        match (exited, status) {
+            (Err(_), _) => Err(format!("timed out after {}s", timeout.as_secs())),
+            (Ok(Err(err)), _) | (_, Err(err)) => Err(err.to_string()),
+            (Ok(Ok(())), Ok(status)) if status.success() => Ok(()),
+            (Ok(Ok(())), Ok(status)) => Err(status.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::program::Command as HookCommand;
+    use std::str::FromStr;
+
+    /// Runs a hook that starts `sleep` in the background, and returns whether it failed along
+    /// with whether the `sleep` is still alive afterwards
+    async fn execute_leaving_child(cmd: &str, timeout: u32) -> (Result<(), String>, bool) {
+        let path = format!("/tmp/taskmaster_tests_hook_child_{timeout}");
+        let _ = std::fs::remove_file(&path);
+        let hook = Hook {
+            events: Vec::new(),
+            cmd: HookCommand::from_str(&cmd.replace("{path}", &path)).unwrap(),
+            timeout,
+        };
+        let runner = HookRunner::new(&[], "taskmaster_test_hook", 0);
+        let mut command = runner.command(&hook, HookEvent::Exited, &HookDetails::default());
+
+        let result =
+            HookRunner::execute(&mut command, b"", Duration::from_secs(timeout as u64)).await;
+        let pid = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Gone, or left as a zombie until its new parent reaps it
+        for _ in 0..50 {
+            let stat =
+                std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())).unwrap_or_default();
+            if matches!(
+                stat.rsplit_once(") ").map(|(_, stat)| &stat[..1]),
+                None | Some("Z")
+            ) {
+                return (result, false);
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        (result, true)
+    }
+
+    #[tokio::test]
+    async fn test_children_killed_on_exit() {
+        let (result, alive) = execute_leaving_child("sh -c 'sleep 30 & echo $! > {path}'", 5).await;
+        assert_eq!(result, Ok(()));
+        assert!(!alive);
+    }
+
+    #[tokio::test]
+    async fn test_children_killed_on_timeout() {
+        let (result, alive) =
+            execute_leaving_child("sh -c 'sleep 30 & echo $! > {path}; wait'", 1).await;
+        assert_eq!(result, Err("timed out after 1s".to_string()));
+        assert!(!alive);
+    }
+}
@@ -0,0 +1,239 @@
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+
+/// Bounded channel from a routine to any number of subscribers, possibly none.
+///
+/// Sending never blocks nor fails: when a subscriber falls more than `capacity` messages behind,
+/// the oldest messages it did not receive yet are dropped and counted in its `dropped` counter.
+/// Messages sent while nobody is subscribed are simply lost.
+#[derive(Debug)]
+pub struct Hub<T> {
+    sender: broadcast::Sender<T>,
+}
+
+impl<T> Clone for Hub<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Hub<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            sender: broadcast::channel(capacity).0,
+        }
+    }
+
+    pub fn send(&self, message: T) {
+        // Only fails when there is no subscriber, which is not an error
+        let _ = self.sender.send(message);
+    }
+
+    /// Subscribes to the messages sent from now on.
+    pub fn subscribe(&self) -> Subscriber<T> {
+        Subscriber {
+            receiver: self.sender.subscribe(),
+            dropped: 0,
+        }
+    }
+
+    /// Returns a handle to subscribe later on, which does not keep the subscribers from seeing
+    /// the end of the channel.
+    pub fn downgrade(&self) -> WeakHub<T> {
+        WeakHub {
+            sender: self.sender.downgrade(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WeakHub<T> {
+    sender: broadcast::WeakSender<T>,
+}
+
+impl<T: Clone> WeakHub<T> {
+    /// Subscribes to the messages sent from now on, or returns `None` if every `Hub` is dropped.
+    pub fn subscribe(&self) -> Option<Subscriber<T>> {
+        self.sender
+            .upgrade()
+            .map(|sender| Hub { sender }.subscribe())
+    }
+}
+
+#[derive(Debug)]
+pub struct Subscriber<T> {
+    receiver: broadcast::Receiver<T>,
+    dropped: u64,
+}
+
+impl<T: Clone> Subscriber<T> {
+    /// Receives the next message, or `None` once every `Hub` is dropped and every message has
+    /// been received.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(message) => return Some(message),
+                Err(RecvError::Lagged(count)) => self.dropped += count,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Receives the next message if one is available right away.
+    #[cfg(test)]
+    pub fn try_recv(&mut self) -> Option<T> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(message) => return Some(message),
+                Err(broadcast::error::TryRecvError::Lagged(count)) => self.dropped += count,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// Number of messages dropped because this subscriber was too far behind.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+type Subscribers<T> = Mutex<Vec<mpsc::UnboundedSender<T>>>;
+
+/// Unbounded channel from a routine to any number of subscribers, possibly none, for the messages
+/// that must never be dropped, such as the statuses.
+///
+/// Every subscriber has its own queue, so a slow subscriber only holds back itself. Messages sent
+/// while nobody is subscribed are simply lost.
+#[derive(Debug)]
+pub struct LosslessHub<T> {
+    subscribers: Arc<Subscribers<T>>,
+}
+
+impl<T> Clone for LosslessHub<T> {
+    fn clone(&self) -> Self {
+        Self {
+            subscribers: Arc::clone(&self.subscribers),
+        }
+    }
+}
+
+impl<T: Clone> LosslessHub<T> {
+    pub fn new() -> Self {
+        Self {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn send(&self, message: T) {
+        // Subscribers that dropped their receiver are forgotten
+        lock(&self.subscribers).retain(|subscriber| subscriber.send(message.clone()).is_ok());
+    }
+
+    /// Subscribes to the messages sent from now on. The receiver sees the end of the channel once
+    /// every `LosslessHub` is dropped.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<T> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        lock(&self.subscribers).push(sender);
+        receiver
+    }
+
+    /// Returns a handle to subscribe later on, which does not keep the subscribers from seeing
+    /// the end of the channel.
+    pub fn downgrade(&self) -> WeakLosslessHub<T> {
+        WeakLosslessHub {
+            subscribers: Arc::downgrade(&self.subscribers),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WeakLosslessHub<T> {
+    subscribers: Weak<Subscribers<T>>,
+}
+
+impl<T: Clone> WeakLosslessHub<T> {
+    /// Subscribes to the messages sent from now on, or returns `None` if every `LosslessHub` is
+    /// dropped.
+    pub fn subscribe(&self) -> Option<mpsc::UnboundedReceiver<T>> {
+        self.subscribers
+            .upgrade()
+            .map(|subscribers| LosslessHub { subscribers }.subscribe())
+    }
+}
+
+/// The lock is never held across a panic, but a poisoned one is still usable.
+fn lock<T>(subscribers: &Subscribers<T>) -> MutexGuard<'_, Vec<mpsc::UnboundedSender<T>>> {
+    subscribers
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send_without_subscriber() {
+        let hub = Hub::new(2);
+        hub.send(1);
+
+        let mut subscriber = hub.subscribe();
+        hub.send(2);
+        drop(hub);
+
+        assert_eq!(subscriber.recv().await, Some(2));
+        assert_eq!(subscriber.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest() {
+        let hub = Hub::new(2);
+        let mut subscriber = hub.subscribe();
+        (1..=5).for_each(|message| hub.send(message));
+
+        assert_eq!(subscriber.try_recv(), Some(4));
+        assert_eq!(subscriber.dropped(), 3);
+        assert_eq!(subscriber.recv().await, Some(5));
+        assert_eq!(subscriber.try_recv(), None);
+    }
+
+    #[tokio::test]
+    async fn test_weak_hub() {
+        let hub = Hub::new(2);
+        let weak_hub = hub.downgrade();
+
+        let mut subscriber = weak_hub.subscribe().unwrap();
+        hub.send(1);
+        drop(hub);
+
+        assert_eq!(subscriber.recv().await, Some(1));
+        assert_eq!(subscriber.recv().await, None);
+        assert!(weak_hub.subscribe().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lossless_hub() {
+        let hub = LosslessHub::new();
+        hub.send(0);
+        let weak_hub = hub.downgrade();
+
+        let mut subscriber = hub.subscribe();
+        let mut late_subscriber = weak_hub.subscribe().unwrap();
+        // Far more messages than a `Hub` would keep
+        (1..=1000).for_each(|message| hub.send(message));
+        assert_eq!(late_subscriber.recv().await, Some(1));
+        // A subscriber gone does not keep the others from receiving
+        drop(late_subscriber);
+        hub.send(1001);
+        drop(hub);
+
+        for message in 1..=1001 {
+            assert_eq!(subscriber.recv().await, Some(message));
+        }
+        assert_eq!(subscriber.recv().await, None);
+        assert!(weak_hub.subscribe().is_none());
+    }
+}
@@ -0,0 +1,208 @@
+use super::log_buffer::LogBuffer;
+use super::readiness::OutputMatcher;
+use super::routine::{LogSender, LogSource, OutputFile, Outputs};
+use super::trigger::TriggerMatcher;
+use super::{Log, LogType};
+use crate::config::program::LogFormat;
+use std::sync::Arc;
+use tokio::{
+    io::{self, AsyncBufRead, AsyncBufReadExt},
+    sync::Mutex,
+};
+
+/// Dispatches the lines captured from the outputs of a child process to its output files, its
+/// log buffer and the log channel.
+pub(super) struct Listener {
+    pub stdout_file: Arc<Mutex<OutputFile>>,
+    pub stderr_file: Arc<Mutex<OutputFile>>,
+    pub log_sender: LogSender,
+    pub log_buffer: LogBuffer,
+    pub source: LogSource,
+    pub log_format: LogFormat,
+    /// Lines longer than that are split, 0 for no limit
+    pub max_line_length: usize,
+    /// Readiness matcher every line is checked against, when the program uses a `regex`
+    /// readiness.
+    pub output_matcher: Option<OutputMatcher>,
+    pub trigger_matcher: TriggerMatcher,
+}
+
+impl Listener {
+    ///  Listens to the outputs of a child process and logs them.
+    ///
+    ///  This function reads from both stdout and stderr streams of a child process,
+    ///  splitting the output by newlines and logging each line as it arrives.
+    ///
+    ///  The function uses `tokio::join!` to concurrently read from both streams,
+    ///  continuing until both streams are exhausted (read returns 0 bytes) or an
+    ///  error occurs. The last line is logged even if it was not terminated by a newline
+    ///  character.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `outputs` - An `Outputs` struct containing the stdout and stderr handles
+    ///    from the child process. When stderr is merged into stdout, only stdout is read and
+    ///    its lines are logged as `LogType::Merged` in the stdout file.
+    pub async fn listen(self, outputs: Outputs) {
+        let Some(stderr) = outputs.stderr else {
+            self.listen_and_log(outputs.stdout, &self.stdout_file, LogType::Merged)
+                .await;
+            return;
+        };
+
+        tokio::join!(
+            self.listen_and_log(outputs.stdout, &self.stdout_file, LogType::Stdout),
+            self.listen_and_log(stderr, &self.stderr_file, LogType::Stderr),
+        );
+    }
+
+    async fn listen_and_log<R: AsyncBufRead + Unpin>(
+        &self,
+        mut output: R,
+        output_file: &Mutex<OutputFile>,
+        log_type: LogType,
+    ) {
+        loop {
+            let mut buffer = Vec::new();
+            let bytes_read = read_line(&mut output, &mut buffer, self.max_line_length).await;
+
+            match bytes_read {
+                Ok(0) => break,
+                Ok(_) => {
+                    // The lock is only held while writing so that the file can be reopened in
+                    // between
+                    let mut output_file = output_file.lock().await;
+                    let log = Log::new(log_type.clone(), &buffer, &self.source);
+                    if let Some(output_matcher) = &self.output_matcher {
+                        output_matcher.check(&log.message);
+                    }
+                    self.trigger_matcher.check(&log.message);
+                    self.dispatch_log(log, &mut output_file).await;
+                }
+                Err(err) => {
+                    eprintln!(
+                        "Taskmaster error: {}: Error encountered while reading {}: {err}",
+                        self.source.program_name,
+                        log_type.as_str()
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Sends a log message over the channel and writes it to the appropriate output file.
+    /// This function performs three operations:
+    /// - Write the log message to the corresponding output file (stdout or stderr), in the
+    ///   program `log_format`
+    /// - Keep the log message in the log buffer
+    /// - Send the log message through the log channel to its subscribers, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `log` - A `Log` struct containing the log type, the task's name and the log itself
+    /// * `output` - A `OutputFile` enum that contains the file to write in
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the `OutputFile` and the `LogType` enums are not accorded.
+    /// That should never happen because those structs are both constructed side by side.
+    ///
+    async fn dispatch_log(&self, log: Log, output: &mut OutputFile) {
+        match (output, &log.log_type) {
+            (OutputFile::Stdout(sink), LogType::Stdout | LogType::Merged) => {
+                let _ = sink.write(&log, self.log_format).await.inspect_err(|err| {
+                    eprintln!("Taskmaster error: {}: Failed to write process stdout output to log file: {err}", log.program_name);
+                });
+            }
+            (OutputFile::Stderr(sink), LogType::Stderr) => {
+                let _ = sink.write(&log, self.log_format).await.inspect_err(|err| {
+                    eprintln!("Taskmaster error: {}: Failed to write process stderr output to log file: {err}", log.program_name);
+                });
+            }
+            _ => panic!(
+                "log function was called with different values for output and log_type, expected same values"
+            ),
+        }
+        self.log_buffer.push(log.clone());
+        self.log_sender.send(log);
+    }
+}
+
+/// Reads the bytes of `reader` into `buffer` up to and including the next newline, like
+/// `read_until`, but stops after `max_length` bytes if the line is longer. A `max_length` of 0
+/// does not limit the line length.
+///
+/// Returns the number of bytes read, 0 once the end of the stream is reached.
+async fn read_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    buffer: &mut Vec<u8>,
+    max_length: usize,
+) -> io::Result<usize> {
+    if max_length == 0 {
+        return reader.read_until(b'\n', buffer).await;
+    }
+
+    let mut bytes_read = 0;
+    while bytes_read < max_length {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            break;
+        }
+
+        let available = &available[..available.len().min(max_length - bytes_read)];
+        let (used, found_newline) = match available.iter().position(|byte| *byte == b'\n') {
+            Some(index) => (index + 1, true),
+            None => (available.len(), false),
+        };
+        buffer.extend_from_slice(&available[..used]);
+        reader.consume(used);
+        bytes_read += used;
+
+        if found_newline {
+            break;
+        }
+    }
+    Ok(bytes_read)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    async fn read_lines(input: &[u8], max_length: usize) -> Vec<Vec<u8>> {
+        let mut reader = input;
+        let mut lines = Vec::new();
+        loop {
+            let mut line = Vec::new();
+            if read_line(&mut reader, &mut line, max_length).await.unwrap() == 0 {
+                break lines;
+            }
+            lines.push(line);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_line() {
+        assert_eq!(
+            read_lines(b"line 1\nline 2\nno newline", 0).await,
+            [&b"line 1\n"[..], b"line 2\n", b"no newline"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_line_split() {
+        assert_eq!(
+            read_lines(b"0123456789\n01234\n012", 4).await,
+            [&b"0123"[..], b"4567", b"89\n", b"0123", b"4\n", b"012"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_line_binary() {
+        assert_eq!(
+            read_lines(b"\xff\x00\xfe\n\x80", 2).await,
+            [&b"\xff\x00"[..], b"\xfe\n", b"\x80"]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use super::{Log, LogType};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// Bounded buffer of the last lines captured from a process, shared between its routine, which
+/// fills it, and its `Handle`, from which it is read.
+///
+/// The buffer is kept even when the output files are `/dev/null`, so recent output can always
+/// be retrieved.
+#[derive(Clone)]
+pub struct LogBuffer {
+    logs: Arc<Mutex<VecDeque<Log>>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            logs: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Appends a log, dropping the oldest one if the buffer is full.
+    pub fn push(&self, log: Log) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut logs = self.logs.lock().expect("log buffer mutex poisoned");
+        if logs.len() == self.capacity {
+            logs.pop_front();
+        }
+        logs.push_back(log);
+    }
+
+    /// Returns up to `lines` of the most recent logs, oldest first. When `stream` is given, only
+    /// logs of that stream are returned. Merged logs belong to both stdout and stderr.
+    pub fn tail(&self, lines: usize, stream: Option<&LogType>) -> Vec<Log> {
+        let logs = self.logs.lock().expect("log buffer mutex poisoned");
+        let mut tail: Vec<Log> = logs
+            .iter()
+            .rev()
+            .filter(|log| stream.is_none_or(|stream| log.log_type.belongs_to(stream)))
+            .take(lines)
+            .cloned()
+            .collect();
+        tail.reverse();
+        tail
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Utc;
+
+    fn log(message: &str, log_type: LogType) -> Log {
+        Log {
+            message: message.as_bytes().to_vec(),
+            program_name: "taskmaster_test_task".to_string(),
+            instance: 0,
+            pid: None,
+            log_type,
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn messages(logs: Vec<Log>) -> Vec<String> {
+        logs.into_iter()
+            .map(|log| String::from_utf8(log.message).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_capacity() {
+        let buffer = LogBuffer::new(2);
+        buffer.push(log("1", LogType::Stdout));
+        buffer.push(log("2", LogType::Stdout));
+        buffer.push(log("3", LogType::Stdout));
+
+        assert_eq!(messages(buffer.tail(10, None)), ["2", "3"]);
+        assert_eq!(messages(buffer.tail(1, None)), ["3"]);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let buffer = LogBuffer::new(0);
+        buffer.push(log("1", LogType::Stdout));
+
+        assert!(buffer.tail(10, None).is_empty());
+    }
+
+    #[test]
+    fn test_stream_filter() {
+        let buffer = LogBuffer::new(10);
+        buffer.push(log("out", LogType::Stdout));
+        buffer.push(log("err", LogType::Stderr));
+        buffer.push(log("merged", LogType::Merged));
+
+        assert_eq!(
+            messages(buffer.tail(10, Some(&LogType::Stdout))),
+            ["out", "merged"]
+        );
+        assert_eq!(
+            messages(buffer.tail(10, Some(&LogType::Stderr))),
+            ["err", "merged"]
+        );
+        assert_eq!(messages(buffer.tail(1, Some(&LogType::Stderr))), ["merged"]);
+    }
+}
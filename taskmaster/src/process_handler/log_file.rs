@@ -0,0 +1,178 @@
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{self, AsyncWriteExt},
+};
+
+/// Output file of a process, rotated once it would grow past `max_bytes`.
+///
+/// On rotation `path` is renamed to `path.1`, `path.1` to `path.2`, and so on up to
+/// `path.{backups}`, and a new empty file is created at `path`. With no backups the file is just
+/// truncated. Only regular files are rotated, so special files like `/dev/null` are left alone.
+pub struct LogFile {
+    path: String,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    backups: u32,
+}
+
+impl LogFile {
+    /// Opens the file at `path`, truncating it unless `append` is set. A `max_bytes` of 0
+    /// disables rotation.
+    pub async fn open(path: &str, append: bool, max_bytes: u64, backups: u32) -> io::Result<Self> {
+        let file = Self::open_file(path, append).await?;
+        let size = file.metadata().await?.len();
+
+        Ok(Self {
+            path: path.to_string(),
+            file,
+            size,
+            max_bytes,
+            backups,
+        })
+    }
+
+    async fn open_file(path: &str, append: bool) -> io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .await
+    }
+
+    /// Writes and flushes the whole buffer, rotating the file first if the buffer does not fit
+    /// in it anymore.
+    pub async fn write(&mut self, buffer: &[u8]) -> io::Result<()> {
+        if self.max_bytes != 0 && self.size != 0 && self.size + buffer.len() as u64 > self.max_bytes
+        {
+            self.rotate().await?;
+        }
+
+        self.file.write_all(buffer).await?;
+        // `tokio::fs::File` otherwise keeps the last write in flight in the background
+        self.file.flush().await?;
+        self.size += buffer.len() as u64;
+        Ok(())
+    }
+
+    /// Closes and opens the file at `path` again, for when an external tool such as logrotate
+    /// moved it away.
+    pub async fn reopen(&mut self) -> io::Result<()> {
+        self.file = Self::open_file(&self.path, true).await?;
+        self.size = self.file.metadata().await?.len();
+        Ok(())
+    }
+
+    async fn rotate(&mut self) -> io::Result<()> {
+        if !self.file.metadata().await?.is_file() {
+            return Ok(());
+        }
+
+        for index in (1..self.backups).rev() {
+            let from = format!("{}.{index}", self.path);
+            if fs::try_exists(&from).await? {
+                fs::rename(&from, format!("{}.{}", self.path, index + 1)).await?;
+            }
+        }
+        if self.backups > 0 {
+            fs::rename(&self.path, format!("{}.1", self.path)).await?;
+        }
+
+        self.file = Self::open_file(&self.path, false).await?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn read(path: &str) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    fn remove(path: &str) {
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_rotation() {
+        let path = "/tmp/taskmaster_tests_rotation.log";
+        (1..=3).for_each(|index| remove(&format!("{path}.{index}")));
+
+        let mut file = LogFile::open(path, false, 8, 2).await.unwrap();
+        file.write(b"line 1\n").await.unwrap();
+        file.write(b"line 2\n").await.unwrap();
+        file.write(b"line 3\n").await.unwrap();
+        file.write(b"line 4\n").await.unwrap();
+
+        assert_eq!(read(path), "line 4\n");
+        assert_eq!(read(&format!("{path}.1")), "line 3\n");
+        assert_eq!(read(&format!("{path}.2")), "line 2\n");
+        assert!(!std::path::Path::new(&format!("{path}.3")).exists());
+
+        remove(path);
+        (1..=2).for_each(|index| remove(&format!("{path}.{index}")));
+    }
+
+    #[tokio::test]
+    async fn test_rotation_without_backups() {
+        let path = "/tmp/taskmaster_tests_rotation_without_backups.log";
+        remove(&format!("{path}.1"));
+
+        let mut file = LogFile::open(path, false, 10, 0).await.unwrap();
+        file.write(b"line 1\n").await.unwrap();
+        file.write(b"line 2\n").await.unwrap();
+
+        assert_eq!(read(path), "line 2\n");
+        assert!(!std::path::Path::new(&format!("{path}.1")).exists());
+
+        remove(path);
+    }
+
+    #[tokio::test]
+    async fn test_append() {
+        let path = "/tmp/taskmaster_tests_append.log";
+        std::fs::write(path, "previous run\n").unwrap();
+
+        let mut file = LogFile::open(path, true, 0, 0).await.unwrap();
+        file.write(b"current run\n").await.unwrap();
+        assert_eq!(read(path), "previous run\ncurrent run\n");
+
+        let mut file = LogFile::open(path, false, 0, 0).await.unwrap();
+        file.write(b"next run\n").await.unwrap();
+        assert_eq!(read(path), "next run\n");
+
+        remove(path);
+    }
+
+    #[tokio::test]
+    async fn test_reopen() {
+        let path = "/tmp/taskmaster_tests_reopen.log";
+        let rotated_path = "/tmp/taskmaster_tests_reopen.log.old";
+
+        let mut file = LogFile::open(path, false, 0, 0).await.unwrap();
+        file.write(b"before\n").await.unwrap();
+        std::fs::rename(path, rotated_path).unwrap();
+        file.reopen().await.unwrap();
+        file.write(b"after\n").await.unwrap();
+
+        assert_eq!(read(rotated_path), "before\n");
+        assert_eq!(read(path), "after\n");
+
+        remove(path);
+        remove(rotated_path);
+    }
+
+    #[tokio::test]
+    async fn test_dev_null_is_not_rotated() {
+        let mut file = LogFile::open("/dev/null", false, 1, 1).await.unwrap();
+        file.write(b"line 1\n").await.unwrap();
+        file.write(b"line 2\n").await.unwrap();
+
+        assert!(!std::path::Path::new("/dev/null.1").exists());
+    }
+}
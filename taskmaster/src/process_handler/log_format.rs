@@ -0,0 +1,125 @@
+use super::Log;
+use crate::config::program::LogFormat;
+use chrono::SecondsFormat;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct JsonLog<'a> {
+    timestamp: String,
+    program: &'a str,
+    instance: u32,
+    pid: Option<u32>,
+    stream: &'a str,
+    message: &'a str,
+}
+
+/// Formats a captured line the way it is written to the output file. Every format but `raw`
+/// produces exactly one newline terminated line per log. The message bytes are kept verbatim,
+/// except in `json` where invalid UTF-8 is replaced.
+pub(super) fn format_log(log: &Log, format: LogFormat) -> Vec<u8> {
+    let timestamp = || log.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true);
+    let message = trim_newline(&log.message);
+
+    match format {
+        LogFormat::Raw => log.message.clone(),
+        LogFormat::Timestamped => {
+            let mut line = format!(
+                "{} {}[{}] ",
+                timestamp(),
+                log.log_type.as_str(),
+                log.instance
+            )
+            .into_bytes();
+            line.extend_from_slice(message);
+            line.push(b'\n');
+            line
+        }
+        LogFormat::Json => {
+            let mut line = serde_json::to_vec(&JsonLog {
+                timestamp: timestamp(),
+                program: &log.program_name,
+                instance: log.instance,
+                pid: log.pid,
+                stream: log.log_type.as_str(),
+                // JSON can only hold text
+                message: &String::from_utf8_lossy(message),
+            })
+            .expect("a JsonLog is always serializable");
+            line.push(b'\n');
+            line
+        }
+    }
+}
+
+/// Strips the line terminators at the end of a captured line.
+pub(super) fn trim_newline(message: &[u8]) -> &[u8] {
+    let end = message
+        .iter()
+        .rposition(|byte| !matches!(byte, b'\n' | b'\r'))
+        .map_or(0, |index| index + 1);
+    &message[..end]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::process_handler::LogType;
+    use chrono::{TimeZone, Utc};
+
+    fn log(message: &str) -> Log {
+        Log {
+            message: message.as_bytes().to_vec(),
+            program_name: "nginx".to_string(),
+            instance: 1,
+            pid: Some(4242),
+            log_type: LogType::Stderr,
+            timestamp: Utc.with_ymd_and_hms(2025, 3, 14, 15, 9, 26).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_format_raw() {
+        assert_eq!(
+            format_log(&log("Hello \"taskmaster\"\n"), LogFormat::Raw),
+            b"Hello \"taskmaster\"\n"
+        );
+    }
+
+    #[test]
+    fn test_format_timestamped() {
+        assert_eq!(
+            String::from_utf8(format_log(
+                &log("Hello taskmaster\n"),
+                LogFormat::Timestamped
+            ))
+            .unwrap(),
+            "2025-03-14T15:09:26.000Z stderr[1] Hello taskmaster\n"
+        );
+        assert_eq!(
+            String::from_utf8(format_log(&log("no newline"), LogFormat::Timestamped)).unwrap(),
+            "2025-03-14T15:09:26.000Z stderr[1] no newline\n"
+        );
+    }
+
+    #[test]
+    fn test_format_binary() {
+        let mut binary_log = log("");
+        binary_log.message = b"\xff\x00\n".to_vec();
+        assert_eq!(format_log(&binary_log, LogFormat::Raw), b"\xff\x00\n");
+        assert!(format_log(&binary_log, LogFormat::Timestamped).ends_with(b" \xff\x00\n"));
+        assert!(
+            String::from_utf8(format_log(&binary_log, LogFormat::Json))
+                .unwrap()
+                .contains("\"message\":\"\u{fffd}\\u0000\"")
+        );
+    }
+
+    #[test]
+    fn test_format_json() {
+        assert_eq!(
+            String::from_utf8(format_log(&log("Hello \"taskmaster\"\n"), LogFormat::Json)).unwrap(),
+            "{\"timestamp\":\"2025-03-14T15:09:26.000Z\",\"program\":\"nginx\",\"instance\":1,\
+            \"pid\":4242,\"stream\":\"stderr\",\"message\":\"Hello \\\"taskmaster\\\"\"}\n"
+        );
+    }
+}
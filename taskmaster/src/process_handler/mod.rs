@@ -1,13 +1,28 @@
 mod command;
 mod handle;
+mod hook;
+mod hub;
+mod listener;
+mod log_buffer;
+mod log_file;
+mod log_format;
+mod pipe_sink;
+mod readiness;
 mod routine;
+mod sink;
 mod status;
+mod syslog;
 #[cfg(test)]
 mod tests;
+mod trigger;
 
 pub use handle::Handle;
+pub use hub::Hub;
+pub use log_buffer::LogBuffer;
 #[allow(unused)]
-pub use routine::{Log, LogType, Routine};
+pub use routine::{KillCommand, Log, LogType, Routine, RoutineSpawnError};
 pub use status::Status;
 #[allow(unused)]
 use std::process::Command;
+#[allow(unused)]
+pub use trigger::TriggerEvent;
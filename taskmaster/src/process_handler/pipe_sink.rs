@@ -0,0 +1,172 @@
+use crate::config::program::Command;
+use std::process::Stdio;
+use tokio::{
+    io::{self, AsyncWriteExt},
+    process::{self, Child, ChildStdin},
+    sync::mpsc,
+    time::{Duration, sleep, timeout},
+};
+
+/// Time waited before starting again a companion that exited, so that one that cannot start
+/// is not restarted in a loop
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+/// Time given to the companion to finish once the sink is dropped and its stdin closed, before it
+/// is killed
+const STOP_TIME: Duration = Duration::from_secs(5);
+
+/// Streams the captured lines into the stdin of a companion process, like `logger` or `gzip`.
+///
+/// The companion is supervised by a task of its own, which reaps it and starts it again as soon
+/// as it exits, without touching the program it reads from. Lines written to a companion that
+/// dies before reading them are lost. Its own output is discarded.
+pub struct PipeSink {
+    stdin: ChildStdin,
+    /// Stdin of every companion started again by the supervisor
+    restarted: mpsc::Receiver<ChildStdin>,
+}
+
+impl PipeSink {
+    pub fn open(command: &Command) -> io::Result<Self> {
+        let (child, stdin) = Self::spawn(command)?;
+        let (sender, restarted) = mpsc::channel(1);
+        tokio::spawn(Self::supervise(command.clone(), child, sender));
+        Ok(Self { stdin, restarted })
+    }
+
+    fn spawn(command: &Command) -> io::Result<(Child, ChildStdin)> {
+        let mut child = process::Command::new(&command.exec)
+            .args(&command.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            // The companion is killed if it outlives its supervisor, on shutdown
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take().expect("Sink process stdin not captured");
+        Ok((child, stdin))
+    }
+
+    /// Waits for the companion to exit and starts it again, until the sink is dropped. The last
+    /// companion is then given `STOP_TIME` to finish reading its closed stdin.
+    async fn supervise(command: Command, mut child: Child, sender: mpsc::Sender<ChildStdin>) {
+        loop {
+            tokio::select! {
+                _ = child.wait() => {}
+                _ = sender.closed() => {
+                    if timeout(STOP_TIME, child.wait()).await.is_err() {
+                        let _ = child.kill().await;
+                    }
+                    return;
+                }
+            }
+
+            eprintln!("Taskmaster: output sink `{command}` exited, restarting it");
+            sleep(RESTART_DELAY).await;
+            let stdin = loop {
+                match Self::spawn(&command) {
+                    Ok((restarted, stdin)) => {
+                        child = restarted;
+                        break stdin;
+                    }
+                    Err(err) => {
+                        eprintln!(
+                            "Taskmaster error: Failed to restart output sink `{command}`: {err}"
+                        );
+                        tokio::select! {
+                            _ = sleep(RESTART_DELAY) => {}
+                            _ = sender.closed() => return,
+                        }
+                    }
+                }
+            };
+            if sender.send(stdin).await.is_err() {
+                // The sink is gone, the companion reads a closed stdin
+                let _ = timeout(STOP_TIME, child.wait()).await;
+                return;
+            }
+        }
+    }
+
+    /// Writes the buffer to the companion. The buffer is written again to the next companion if
+    /// the current one exited before it was written.
+    pub async fn write(&mut self, buffer: &[u8]) -> io::Result<()> {
+        while let Ok(stdin) = self.restarted.try_recv() {
+            self.stdin = stdin;
+        }
+
+        match self.stdin.write_all(buffer).await {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {
+                self.stdin = self.restarted.recv().await.ok_or(err)?;
+                self.stdin.write_all(buffer).await
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    async fn read_eventually(path: &str, expected: &str) -> String {
+        let mut content = String::new();
+        for _ in 0..100 {
+            content = std::fs::read_to_string(path).unwrap_or_default();
+            if content == expected {
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        content
+    }
+
+    #[tokio::test]
+    async fn test_write() {
+        let path = "/tmp/taskmaster_tests_pipe_sink_write.log";
+        let _ = std::fs::remove_file(path);
+        let command = Command::from_str(&format!("sh -c 'cat > {path}'")).unwrap();
+
+        let mut sink = PipeSink::open(&command).unwrap();
+        sink.write(b"line 1\n").await.unwrap();
+        sink.write(b"line 2\n").await.unwrap();
+        // The companion finishes on its own once its stdin is closed
+        drop(sink);
+
+        let expected = "line 1\nline 2\n";
+        assert_eq!(read_eventually(path, expected).await, expected);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restart() {
+        let path = "/tmp/taskmaster_tests_pipe_sink_restart.log";
+        let _ = std::fs::remove_file(path);
+        // Every sink process handles a single line then exits
+        let command = Command::from_str(&format!("sh -c 'head -n 1 >> {path}'")).unwrap();
+
+        let mut sink = PipeSink::open(&command).unwrap();
+        for line in ["line 1\n", "line 2\n", "line 3\n"] {
+            sink.write(line.as_bytes()).await.unwrap();
+            sleep(Duration::from_millis(200)).await;
+        }
+
+        let expected = "line 1\nline 2\nline 3\n";
+        assert_eq!(read_eventually(path, expected).await, expected);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restart_eagerly() {
+        let path = "/tmp/taskmaster_tests_pipe_sink_restart_eagerly.log";
+        let _ = std::fs::remove_file(path);
+        let command = Command::from_str(&format!("sh -c 'echo started >> {path}'")).unwrap();
+
+        let sink = PipeSink::open(&command).unwrap();
+        // Started again without anything written to it
+        let expected = "started\nstarted\n";
+        assert_eq!(read_eventually(path, expected).await, expected);
+        drop(sink);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,254 @@
+use super::log_format::trim_newline;
+use crate::config::{Pattern, Program, program::Readiness};
+use libc::unistd::getuid;
+use std::{
+    fs::{self, DirBuilder},
+    os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+use tokio::{
+    io,
+    net::{TcpStream, UnixDatagram},
+    sync::Notify,
+    time::{Duration, sleep, timeout},
+};
+
+const TCP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+/// Size of `sun_path` in `struct sockaddr_un`, which holds the socket path and its nul byte
+#[cfg(target_os = "linux")]
+const SUN_PATH_LEN: usize = 108;
+#[cfg(not(target_os = "linux"))]
+const SUN_PATH_LEN: usize = 104;
+
+/// Waits for a freshly spawned process to become ready, according to the `readiness` strategy of
+/// its program.
+pub(super) enum Probe {
+    StartTime(Duration),
+    Output(Arc<Notify>),
+    Tcp(String),
+    Notify(NotifySocket),
+}
+
+/// Checks the lines captured from the process output against the readiness pattern and wakes up
+/// the matching `Probe::Output` on the first match.
+pub(super) struct OutputMatcher {
+    pattern: Pattern,
+    notify: Arc<Notify>,
+}
+
+impl OutputMatcher {
+    pub(super) fn check(&self, line: &[u8]) {
+        if self
+            .pattern
+            .is_match(&String::from_utf8_lossy(trim_newline(line)))
+        {
+            self.notify.notify_one();
+        }
+    }
+}
+
+impl Probe {
+    /// Creates the probe for one run of the program. Also returns the matcher to hand over to the
+    /// output listener when the program is configured with a `regex` readiness.
+    pub(super) fn new(config: &Program) -> io::Result<(Self, Option<OutputMatcher>)> {
+        Ok(match config.readiness() {
+            Readiness::StartTime => (
+                Self::StartTime(Duration::from_secs(*config.start_time() as u64)),
+                None,
+            ),
+            Readiness::Regex { pattern, .. } => {
+                let notify = Arc::new(Notify::new());
+                (
+                    Self::Output(Arc::clone(&notify)),
+                    Some(OutputMatcher {
+                        pattern: pattern.clone(),
+                        notify,
+                    }),
+                )
+            }
+            Readiness::Tcp { address, .. } => (Self::Tcp(address.clone()), None),
+            Readiness::Notify { .. } => (Self::Notify(NotifySocket::bind(config.name())?), None),
+        })
+    }
+
+    /// Path of the socket to expose to the child through `NOTIFY_SOCKET`, if any.
+    pub(super) fn notify_socket_path(&self) -> Option<&Path> {
+        match self {
+            Self::Notify(socket) => Some(&socket.path),
+            _ => None,
+        }
+    }
+
+    /// Resolves once the process is considered ready. Never resolves if the readiness condition
+    /// can not be met, the caller is expected to race it against the process exit and the
+    /// readiness `timeout`.
+    pub(super) async fn ready(&mut self) {
+        match self {
+            Self::StartTime(start_time) if start_time.is_zero() => {}
+            Self::StartTime(start_time) => sleep(*start_time).await,
+            Self::Output(notify) => notify.notified().await,
+            Self::Tcp(addr) => loop {
+                if let Ok(Ok(_)) = timeout(TCP_CONNECT_TIMEOUT, TcpStream::connect(&*addr)).await {
+                    break;
+                }
+                sleep(TCP_POLL_INTERVAL).await;
+            },
+            Self::Notify(socket) => socket.wait_ready().await,
+        }
+    }
+}
+
+/// Datagram socket on which the child sends its sd_notify messages, bound in a directory only the
+/// user running taskmaster can access, see `runtime_dir`. The socket file is removed when dropped.
+pub(super) struct NotifySocket {
+    socket: UnixDatagram,
+    path: PathBuf,
+}
+
+impl NotifySocket {
+    fn bind(program_name: &str) -> io::Result<Self> {
+        static SOCKET_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let path = runtime_dir()?.join(format!(
+            "{}-{program_name}-{}.notify",
+            std::process::id(),
+            SOCKET_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        if path.as_os_str().len() >= SUN_PATH_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("notify socket path too long: {}", path.display()),
+            ));
+        }
+        let _ = fs::remove_file(&path);
+
+        Ok(Self {
+            socket: UnixDatagram::bind(&path)?,
+            path,
+        })
+    }
+
+    async fn wait_ready(&self) {
+        let mut buffer = [0; 4096];
+        loop {
+            match self.socket.recv(&mut buffer).await {
+                Ok(len) if is_ready_message(&buffer[..len]) => break,
+                Ok(_) => {}
+                Err(err) => {
+                    eprintln!(
+                        "Taskmaster error: failed to read from notify socket {}: {err}",
+                        self.path.display()
+                    );
+                    std::future::pending::<()>().await;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for NotifySocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Directory of the notify sockets, `taskmaster` in `XDG_RUNTIME_DIR` or `taskmaster-<uid>` in
+/// the temporary directory. It is created with mode 0700, and refused if it exists but is not
+/// private to the current user, since anyone able to write in it could fake readiness.
+fn runtime_dir() -> io::Result<PathBuf> {
+    let uid = unsafe { getuid() };
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) => PathBuf::from(runtime_dir).join("taskmaster"),
+        None => std::env::temp_dir().join(format!("taskmaster-{uid}")),
+    };
+
+    match DirBuilder::new().mode(0o700).create(&dir) {
+        Err(err) if err.kind() != io::ErrorKind::AlreadyExists => return Err(err),
+        _ => {}
+    }
+    // Not followed, a link could point anywhere
+    let metadata = fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.permissions().mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "{} must be a directory private to the user running taskmaster",
+                dir.display()
+            ),
+        ));
+    }
+    Ok(dir)
+}
+
+/// sd_notify messages are newline separated `KEY=VALUE` assignments.
+fn is_ready_message(datagram: &[u8]) -> bool {
+    datagram
+        .split(|byte| *byte == b'\n')
+        .any(|assignment| assignment == b"READY=1")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::FutureExt;
+
+    #[test]
+    fn test_is_ready_message() {
+        assert!(is_ready_message(b"READY=1"));
+        assert!(is_ready_message(b"STATUS=Listening\nREADY=1\n"));
+        assert!(!is_ready_message(b"READY=0"));
+        assert!(!is_ready_message(b"STATUS=READY=1"));
+        assert!(!is_ready_message(b""));
+    }
+
+    #[tokio::test]
+    async fn test_notify_socket() {
+        let socket = NotifySocket::bind("notify_test").unwrap();
+        let path = socket.path.clone();
+
+        let client = UnixDatagram::unbound().unwrap();
+        client.send_to(b"STATUS=starting", &path).await.unwrap();
+        client.send_to(b"READY=1", &path).await.unwrap();
+        socket.wait_ready().await;
+
+        drop(socket);
+        assert!(!path.exists());
+
+        let mode = fs::metadata(path.parent().unwrap())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    #[test]
+    fn test_notify_socket_path_too_long() {
+        let name = "a".repeat(SUN_PATH_LEN);
+        let err = NotifySocket::bind(&name).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_output_matcher() {
+        let notify = Arc::new(Notify::new());
+        let matcher = OutputMatcher {
+            pattern: Pattern::try_from("^listening on [0-9]+$").unwrap(),
+            notify: Arc::clone(&notify),
+        };
+
+        matcher.check(b"starting\n");
+        matcher.check(b"listening on 8080 soon\r\n");
+        assert!(notify.notified().now_or_never().is_none());
+
+        // `$` matches before the line ending, be it LF or CRLF
+        matcher.check(b"listening on 8080\n");
+        assert!(notify.notified().now_or_never().is_some());
+        matcher.check(b"listening on 8080\r\n");
+        assert!(notify.notified().now_or_never().is_some());
+    }
+}
@@ -1,95 +1,280 @@
+use super::hook::{HookDetails, HookRunner};
+use super::hub::{Hub, LosslessHub, Subscriber};
+use super::listener::Listener;
+use super::log_buffer::LogBuffer;
+use super::readiness::{OutputMatcher, Probe};
+use super::sink::Sink;
+use super::trigger::{FiredTrigger, TriggerEvent, TriggerMatcher};
 use super::{Handle, Status, command};
-use crate::config::program::{AutoRestart, Program};
+use crate::config::HookEvent;
+use crate::config::program::{AutoRestart, Program, TriggerAction};
+use chrono::{DateTime, Utc};
+use commands::{LogLine, LogStream};
 use libc::signal::kill;
 use libc::unistd::{mode_t, umask};
 use signal::Signal;
+use std::os::unix::process::ExitStatusExt;
 use std::panic;
-use std::process::Stdio;
+use std::process::{ExitStatus, Stdio};
 use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
 use thiserror::Error;
-use tokio::io::AsyncBufRead;
 use tokio::process::Command;
-use tokio::sync::oneshot;
-use tokio::{fs::File, io::AsyncWriteExt};
+use tokio::sync::{oneshot, watch};
 use tokio::{
-    io::{AsyncBufReadExt, BufReader, Error},
-    process::{Child, ChildStderr, ChildStdout},
+    io::{AsyncRead, BufReader, Error},
+    net::unix::pipe,
+    process::Child,
     sync::{Mutex, mpsc},
-    time::{Duration, Instant},
 };
 
-#[derive(Clone, Debug)]
+/// Number of logs kept for the subscribers of a process that are late to receive them, older
+/// logs are dropped.
+const LOG_CHANNEL_CAPACITY: usize = 1024;
+/// Number of trigger events kept for the subscribers of a process that are late to receive them,
+/// older events are dropped.
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+/// Minimum time between two restarts of a process by a `restart` trigger, the triggers firing in
+/// between are ignored
+const TRIGGER_RESTART_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LogType {
     Stdout,
     Stderr,
+    /// Stdout and stderr of a program with `redirect_stderr`, captured through the same pipe
+    Merged,
+}
+
+impl LogType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogType::Stdout => "stdout",
+            LogType::Stderr => "stderr",
+            LogType::Merged => "merged",
+        }
+    }
+
+    /// Whether a log of this type is part of `stream`, merged logs are part of every stream.
+    pub fn belongs_to(&self, stream: &LogType) -> bool {
+        self == stream || *self == LogType::Merged
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Log {
-    pub message: String,
+    /// Raw bytes of the line, including its newline if it was not split
+    pub message: Vec<u8>,
     pub program_name: String,
+    pub instance: u32,
+    pub pid: Option<u32>,
     pub log_type: LogType,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Process a log line was captured from.
+#[derive(Clone, Debug)]
+pub struct LogSource {
+    pub program_name: String,
+    pub instance: u32,
+    pub pid: Option<u32>,
+}
+
+impl From<LogStream> for LogType {
+    fn from(stream: LogStream) -> Self {
+        match stream {
+            LogStream::Stdout => LogType::Stdout,
+            LogStream::Stderr => LogType::Stderr,
+            LogStream::Merged => LogType::Merged,
+        }
+    }
+}
+
+impl From<&LogType> for LogStream {
+    fn from(log_type: &LogType) -> Self {
+        match log_type {
+            LogType::Stdout => LogStream::Stdout,
+            LogType::Stderr => LogStream::Stderr,
+            LogType::Merged => LogStream::Merged,
+        }
+    }
+}
+
+impl From<Log> for LogLine {
+    fn from(log: Log) -> Self {
+        LogLine {
+            stream: LogStream::from(&log.log_type),
+            program: log.program_name,
+            instance: log.instance,
+            message: String::from_utf8_lossy(&log.message).into_owned(),
+        }
+    }
 }
 
 impl Log {
-    fn new(output_file: &OutputFile, buffer: &[u8], name: &str) -> Self {
-        match output_file {
-            OutputFile::Stdout(_) => Log {
-                message: String::from_utf8_lossy(buffer).to_string(),
-                program_name: name.to_string(),
-                log_type: LogType::Stdout,
-            },
-            OutputFile::Stderr(_) => Log {
-                message: String::from_utf8_lossy(buffer).to_string(),
-                program_name: name.to_string(),
-                log_type: LogType::Stderr,
-            },
+    pub(super) fn new(log_type: LogType, buffer: &[u8], source: &LogSource) -> Self {
+        Log {
+            message: buffer.to_vec(),
+            program_name: source.program_name.clone(),
+            instance: source.instance,
+            pid: source.pid,
+            log_type,
+            timestamp: Utc::now(),
         }
     }
 }
 
 pub type StatusReceiver = mpsc::UnboundedReceiver<Status>;
-pub type LogReceiver = mpsc::UnboundedReceiver<Log>;
-pub type StatusSender = mpsc::UnboundedSender<Status>;
-pub type LogSender = mpsc::UnboundedSender<Log>;
-pub type KillCommandReceiver = mpsc::Receiver<oneshot::Sender<ProcessState>>;
-pub type KillCommandSender = mpsc::Sender<oneshot::Sender<ProcessState>>;
+pub type LogReceiver = Subscriber<Log>;
+pub type StatusSender = LosslessHub<Status>;
+pub type LogSender = Hub<Log>;
+pub type KillCommandReceiver = mpsc::Receiver<KillCommand>;
+pub type KillCommandSender = mpsc::Sender<KillCommand>;
+
+/// Asks a routine to stop its process for good.
+#[derive(Debug)]
+pub struct KillCommand {
+    /// Overrides `stoptime` for this stop
+    pub timeout: Option<u32>,
+    /// Sends SIGKILL right away instead of `stopsignal`
+    pub force: bool,
+    /// Tells whether the process was still running when the signal was sent
+    pub sender: oneshot::Sender<ProcessState>,
+}
+
+#[cfg(test)]
+impl KillCommand {
+    pub fn new(sender: oneshot::Sender<ProcessState>) -> Self {
+        Self {
+            timeout: None,
+            force: false,
+            sender,
+        }
+    }
+}
+
+pub(super) type OutputReader = BufReader<Box<dyn AsyncRead + Send + Unpin>>;
 
 pub struct Outputs {
-    stdout: BufReader<ChildStdout>,
-    stderr: BufReader<ChildStderr>,
+    pub(super) stdout: OutputReader,
+    /// `None` when stderr is merged into stdout
+    pub(super) stderr: Option<OutputReader>,
 }
 
 impl Outputs {
-    pub fn new(child: &mut Child) -> Self {
+    /// Takes the output pipes of the child, or uses `merged` as the only output when both stdout
+    /// and stderr were redirected to it.
+    pub fn new(child: &mut Child, merged: Option<pipe::Receiver>) -> Self {
+        if let Some(merged) = merged {
+            return Self {
+                stdout: BufReader::new(Box::new(merged)),
+                stderr: None,
+            };
+        }
+
         Self {
-            stdout: BufReader::new(
+            stdout: BufReader::new(Box::new(
                 child
                     .stdout
                     .take()
                     .expect("Child process stdout not captured"),
-            ),
-            stderr: BufReader::new(
+            )),
+            stderr: Some(BufReader::new(Box::new(
                 child
                     .stderr
                     .take()
                     .expect("Child process stderr not captured"),
-            ),
+            ))),
         }
     }
 }
 
-enum OutputFile {
-    Stdout(File),
-    Stderr(File),
+pub enum OutputFile {
+    Stdout(Sink),
+    Stderr(Sink),
+}
+
+impl OutputFile {
+    async fn reopen(&mut self) -> Result<(), Error> {
+        match self {
+            OutputFile::Stdout(sink) | OutputFile::Stderr(sink) => sink.reopen().await,
+        }
+    }
+}
+
+/// Output files of a routine, shared with its `Handle` so they can be reopened from outside.
+#[derive(Clone)]
+pub struct OutputFiles {
+    stdout: Arc<Mutex<OutputFile>>,
+    stderr: Arc<Mutex<OutputFile>>,
+}
+
+impl OutputFiles {
+    /// Opens the outputs of `config`, stderr going to the stdout file with `redirect_stderr`.
+    pub async fn open(config: &Program) -> Result<Self, RoutineSpawnError> {
+        let stdout = Arc::new(Mutex::new(OutputFile::Stdout(
+            Sink::open(
+                config.stdout(),
+                config,
+                *config.stdout_max_bytes(),
+                *config.stdout_backups(),
+            )
+            .await
+            .map_err(|error| RoutineSpawnError::OpeningStdoutFile {
+                program_name: config.name().to_string(),
+                error,
+            })?,
+        )));
+        let stderr = if *config.redirect_stderr() {
+            Arc::clone(&stdout)
+        } else {
+            Arc::new(Mutex::new(OutputFile::Stderr(
+                Sink::open(
+                    config.stderr(),
+                    config,
+                    *config.stderr_max_bytes(),
+                    *config.stderr_backups(),
+                )
+                .await
+                .map_err(|error| RoutineSpawnError::OpeningStderrFile {
+                    program_name: config.name().to_string(),
+                    error,
+                })?,
+            )))
+        };
+        Ok(Self { stdout, stderr })
+    }
+
+    /// Reopens both output files, see `LogFile::reopen` and `SyslogSink::reopen`.
+    pub async fn reopen(&self) -> Result<(), Error> {
+        self.stdout.lock().await.reopen().await?;
+        if Arc::ptr_eq(&self.stdout, &self.stderr) {
+            return Ok(());
+        }
+        self.stderr.lock().await.reopen().await
+    }
 }
 
 pub struct Routine {
     status_sender: StatusSender,
     log_sender: LogSender,
     kill_command_receiver: KillCommandReceiver,
+    log_buffer: LogBuffer,
     config: Program,
+    instance: u32,
     start_attempts: u32,
+    /// Set by a `restart` trigger, to restart the process whatever its exit status
+    restart_requested: bool,
+    /// When a `restart` trigger last restarted the process
+    last_trigger_restart: Option<Instant>,
+    /// Set by a kill command, to never restart the process
+    stop_requested: bool,
+    event_sender: Hub<TriggerEvent>,
+    hooks: HookRunner,
+    /// Pid of the process while it is alive
+    pid_sender: watch::Sender<Option<u32>>,
+    /// Whether the process is alive and ready, i.e. `Running`
+    ready_sender: watch::Sender<bool>,
     command: Command,
 }
 
@@ -108,6 +293,13 @@ pub enum RoutineSpawnError {
     },
 }
 
+/// How a process supervised by `handle_running_child` ended.
+enum Outcome {
+    Exited(Status),
+    Kill(KillCommand),
+    Restart,
+}
+
 #[derive(Debug)]
 pub enum ProcessState {
     Running,
@@ -116,46 +308,78 @@ pub enum ProcessState {
 
 #[allow(dead_code)] //TODO: Remove that
 impl Routine {
-    pub async fn spawn(config: Program) -> Result<Handle, RoutineSpawnError> {
-        let (status_sender, status_receiver) = mpsc::unbounded_channel();
-        let (log_sender, log_receiver) = mpsc::unbounded_channel();
+    /// Spawns the routine supervising the `instance`-th process of the program (starting from 0),
+    /// with output files of its own.
+    pub async fn spawn(config: Program, instance: u32) -> Result<Handle, RoutineSpawnError> {
+        let output_files = OutputFiles::open(&config).await?;
+        Ok(Self::spawn_with_output_files(
+            config,
+            instance,
+            output_files,
+        ))
+    }
+
+    /// Spawns the routine with the output files of another instance of the program, so that the
+    /// instances append to the same files instead of each truncating and rotating them.
+    pub fn spawn_with_output_files(
+        config: Program,
+        instance: u32,
+        output_files: OutputFiles,
+    ) -> Handle {
+        let status_sender = LosslessHub::new();
+        let status_receiver = status_sender.subscribe();
+        let log_sender = Hub::new(LOG_CHANNEL_CAPACITY);
+        let log_receiver = log_sender.subscribe();
         let (kill_command_sender, kill_command_receiver) = mpsc::channel(1);
-        let stdout_file = Arc::new(Mutex::new(OutputFile::Stdout(
-            File::create(config.stdout()).await.map_err(|error| {
-                RoutineSpawnError::OpeningStdoutFile {
-                    program_name: config.name().to_string(),
-                    error,
-                }
-            })?,
-        )));
-        let stderr_file = Arc::new(Mutex::new(OutputFile::Stderr(
-            File::create(config.stderr()).await.map_err(|error| {
-                RoutineSpawnError::OpeningStderrFile {
-                    program_name: config.name().to_string(),
-                    error,
-                }
-            })?,
-        )));
+        let log_buffer = LogBuffer::new(*config.log_buffer_lines());
+        let stdout_file = Arc::clone(&output_files.stdout);
+        let stderr_file = Arc::clone(&output_files.stderr);
         let command = command::create_command(&config);
 
+        let routine_log_buffer = log_buffer.clone();
+        let status_hub = status_sender.downgrade();
+        let log_hub = log_sender.downgrade();
+        let event_sender = Hub::new(EVENT_CHANNEL_CAPACITY);
+        let event_hub = event_sender.downgrade();
+        let hooks = HookRunner::new(config.hooks(), config.name(), instance);
+        let (pid_sender, pid_receiver) = watch::channel(None);
+        let (ready_sender, ready_receiver) = watch::channel(false);
         let join_handle = tokio::spawn(async move {
             Self {
                 config,
+                instance,
                 status_sender,
                 log_sender,
+                log_buffer: routine_log_buffer,
                 kill_command_receiver,
                 start_attempts: 0,
+                restart_requested: false,
+                last_trigger_restart: None,
+                stop_requested: false,
+                event_sender,
+                hooks,
+                pid_sender,
+                ready_sender,
                 command,
             }
             .routine(stdout_file, stderr_file)
             .await;
         });
-        Ok(Handle::new(
+        Handle {
+            instance,
             join_handle,
             status_receiver,
             log_receiver,
             kill_command_sender,
-        ))
+            output_files,
+            log_buffer,
+            status_hub,
+            log_hub,
+            pid: pid_receiver,
+            ready: ready_receiver,
+            event_hub,
+            stopping: false,
+        }
     }
 
     async fn routine(
@@ -164,15 +388,14 @@ impl Routine {
         stderr_file: Arc<Mutex<OutputFile>>,
     ) {
         loop {
-            let start_time = Instant::now();
-
             let status = self
                 .run_program(Arc::clone(&stdout_file), Arc::clone(&stderr_file))
                 .await;
 
-            let should_try_restart = self.should_try_restart(start_time, &status);
+            let should_try_restart = self.should_try_restart(&status);
+            self.run_exit_hooks(&status, should_try_restart);
 
-            Self::send_new_status_to_task_manager(&mut self.status_sender, status);
+            Self::send_new_status_to_task_manager(&self.status_sender, status);
 
             if !should_try_restart {
                 break;
@@ -185,6 +408,17 @@ impl Routine {
         stdout_file: Arc<Mutex<OutputFile>>,
         stderr_file: Arc<Mutex<OutputFile>>,
     ) -> Status {
+        let (probe, output_matcher) = match Probe::new(&self.config) {
+            Ok(probe) => probe,
+            Err(err) => {
+                self.start_attempts += 1;
+                return Status::FailedToSpawn(Arc::new(err));
+            }
+        };
+        if let Some(path) = probe.notify_socket_path() {
+            self.command.env("NOTIFY_SOCKET", path);
+        }
+
         let child = {
             // Save the current umask and restore it after the child process is spawned.
             // We need to do this because the child process inherits the umask of the parent process.
@@ -214,46 +448,112 @@ impl Routine {
         };
 
         match child {
-            Ok(child) => {
-                Self::send_new_status_to_task_manager(&mut self.status_sender, Status::Starting);
-                self.handle_running_child(child, stdout_file, stderr_file)
-                    .await
+            Ok((mut child, merged_output)) => {
+                Self::send_new_status_to_task_manager(&self.status_sender, Status::Starting);
+                self.hooks.run(
+                    HookEvent::Started,
+                    HookDetails {
+                        pid: child.id(),
+                        ..Default::default()
+                    },
+                );
+                let outputs = Outputs::new(&mut child, merged_output);
+                self.handle_running_child(
+                    child,
+                    outputs,
+                    probe,
+                    output_matcher,
+                    stdout_file,
+                    stderr_file,
+                )
+                .await
             }
-            Err(err) => Status::FailedToSpawn(err),
+            Err(err) => Status::FailedToSpawn(Arc::new(err)),
         }
     }
 
     async fn handle_running_child(
         &mut self,
         mut child: Child,
+        outputs: Outputs,
+        mut probe: Probe,
+        output_matcher: Option<OutputMatcher>,
         stdout_file: Arc<Mutex<OutputFile>>,
         stderr_file: Arc<Mutex<OutputFile>>,
     ) -> Status {
-        let outputs = Outputs::new(&mut child);
-        let listen_task = tokio::spawn(Self::listen(
-            outputs,
+        let pid = child.id();
+        self.pid_sender.send_replace(pid);
+        let (trigger_matcher, mut fired_triggers) =
+            TriggerMatcher::new(self.config.name().clone(), self.config.triggers().clone());
+        let listener = Listener {
             stdout_file,
             stderr_file,
-            self.log_sender.clone(),
-            self.config.name().clone(),
-        ));
-
-        let status = tokio::select! {
-            status = Self::wait_for_child(
+            log_sender: self.log_sender.clone(),
+            log_buffer: self.log_buffer.clone(),
+            source: LogSource {
+                program_name: self.config.name().clone(),
+                instance: self.instance,
+                pid,
+            },
+            log_format: *self.config.log_format(),
+            max_line_length: *self.config.max_line_length(),
+            output_matcher,
+            trigger_matcher,
+        };
+        let listen_task = tokio::spawn(listener.listen(outputs));
+
+        let outcome = {
+            let status_sender = self.status_sender.clone();
+            let hooks = self.hooks.clone();
+            let pid_sender = self.pid_sender.clone();
+            let ready_sender = self.ready_sender.clone();
+            let wait = Self::wait_for_child(
                 &mut child,
-                *self.config.start_time(),
-                &mut self.status_sender
-            ) => {
-                status
+                &mut probe,
+                &status_sender,
+                &hooks,
+                &pid_sender,
+                &ready_sender,
+                self.config.readiness().timeout(),
+            );
+            tokio::pin!(wait);
+
+            loop {
+                tokio::select! {
+                    status = &mut wait => break Outcome::Exited(status),
+
+                    // Nobody can ask for the process to be killed anymore once the handle is
+                    // dropped
+                    Some(command) = self.kill_command_receiver.recv() => break Outcome::Kill(command),
+
+                    Some(fired) = fired_triggers.recv() => {
+                        if let Some(outcome) = self.handle_fired_trigger(fired, pid) {
+                            break outcome;
+                        }
+                    }
+                }
             }
+        };
 
-            sender = self.kill_command_receiver.recv() => {
-                Self::kill_subprocess(
-                    sender.expect("receiver was dropped"),
-                    &mut child,
-                    self.config.stop_signal()
-                );
-                Status::Exited(child.wait().await.expect("error waiting for child"))
+        // The process exited, or is about to
+        let was_ready = self.ready_sender.send_replace(false);
+        let status = match outcome {
+            Outcome::Exited(status) => status,
+            Outcome::Kill(command) => self.stop_child(&mut child, command).await,
+            Outcome::Restart => {
+                self.restart_requested = true;
+                // Restarting a process that was not ready yet counts as another start attempt
+                if was_ready {
+                    self.start_attempts = 0;
+                }
+                // Stopped like a stop command would, the process may well be wedged
+                let stop_signal = *self.config.stop_signal();
+                let stop_time = *self.config.stop_time();
+                Status::Exited(
+                    self.terminate(&mut child, stop_signal, stop_time)
+                        .await
+                        .expect("error waiting for child"),
+                )
             }
         };
 
@@ -263,53 +563,196 @@ impl Routine {
         status
     }
 
-    fn kill_subprocess(
-        sender: oneshot::Sender<ProcessState>,
-        child: &mut Child,
-        stop_signal: &Signal,
-    ) {
-        let Some(pid) = child.id() else {
-            sender
-                .send(ProcessState::Stopped)
-                .expect("receiver was dropped");
-            return;
+    /// Acts upon a trigger that fired while the process is alive. Returns how the process should
+    /// end, if it should.
+    fn handle_fired_trigger(&mut self, fired: FiredTrigger, pid: Option<u32>) -> Option<Outcome> {
+        match fired.action {
+            TriggerAction::Restart => {
+                if let Some(last_restart) = self.last_trigger_restart
+                    && last_restart.elapsed() < TRIGGER_RESTART_INTERVAL
+                {
+                    eprintln!(
+                        "Taskmaster error: {}: Restart trigger `{}` ignored, the process was restarted less than {}s ago",
+                        self.config.name(),
+                        fired.pattern,
+                        TRIGGER_RESTART_INTERVAL.as_secs()
+                    );
+                    return None;
+                }
+                self.last_trigger_restart = Some(Instant::now());
+                return Some(Outcome::Restart);
+            }
+            TriggerAction::Signal { signal } => {
+                if let Some(pid) = pid {
+                    unsafe { kill(pid as i32, signal as i32) };
+                }
+            }
+            TriggerAction::Unhealthy => self.status_sender.send(Status::Unhealthy {
+                pattern: fired.pattern,
+                line: fired.line,
+            }),
+            TriggerAction::Event => self.event_sender.send(TriggerEvent {
+                program_name: self.config.name().clone(),
+                instance: self.instance,
+                pattern: fired.pattern,
+                line: fired.line,
+            }),
+        }
+        None
+    }
+
+    /// Stops the process with its `stopsignal` and waits for it to exit. The process group is
+    /// killed if the process is still alive after `stoptime` seconds, or right away when forced or
+    /// when `stoptime` is 0.
+    async fn stop_child(&mut self, child: &mut Child, command: KillCommand) -> Status {
+        self.stop_requested = true;
+        let KillCommand {
+            timeout,
+            force,
+            sender,
+        } = command;
+
+        let state = match child.id() {
+            Some(_) => ProcessState::Running,
+            None => ProcessState::Stopped,
+        };
+        sender.send(state).expect("receiver was dropped");
+
+        let stop_signal = *self.config.stop_signal();
+        let stop_time = if force {
+            0
+        } else {
+            timeout.unwrap_or(*self.config.stop_time())
         };
+        Status::Exited(
+            self.terminate(child, stop_signal, stop_time)
+                .await
+                .expect("error waiting for child"),
+        )
+    }
+
+    /// Sends `signal` to the process and waits for it to exit. Its process group is killed if it
+    /// is still alive after `stop_time` seconds, or right away if `stop_time` is 0.
+    async fn terminate(
+        &self,
+        child: &mut Child,
+        signal: Signal,
+        stop_time: u32,
+    ) -> std::io::Result<ExitStatus> {
+        if stop_time == 0 {
+            Self::kill_group(&self.pid_sender);
+            return Self::reap(child, &self.pid_sender).await;
+        }
+        if let Some(pid) = *self.pid_sender.borrow() {
+            unsafe { kill(pid as i32, signal as i32) };
+        }
+
+        let reap = Self::reap(child, &self.pid_sender);
+        tokio::pin!(reap);
+        match tokio::time::timeout(Duration::from_secs(stop_time as u64), &mut reap).await {
+            Ok(exit_status) => exit_status,
+            Err(_) => {
+                Self::kill_group(&self.pid_sender);
+                reap.await
+            }
+        }
+    }
 
-        unsafe { kill(pid as i32, *stop_signal as i32) };
-        sender
-            .send(ProcessState::Running)
-            .expect("receiver was dropped");
+    /// Sends SIGKILL to the process and to its children, which are part of the group it leads
+    fn kill_group(pid_sender: &watch::Sender<Option<u32>>) {
+        // The process is not reaped yet, so its pid cannot have been reused
+        if let Some(pid) = *pid_sender.borrow() {
+            unsafe { kill(-(pid as i32), Signal::SIGKILL as i32) };
+        }
     }
 
     async fn wait_for_child(
         child: &mut Child,
-        start_time: u32,
-        status_sender: &mut StatusSender,
+        probe: &mut Probe,
+        status_sender: &StatusSender,
+        hooks: &HookRunner,
+        pid_sender: &watch::Sender<Option<u32>>,
+        ready_sender: &watch::Sender<bool>,
+        ready_timeout: Option<u32>,
     ) -> Status {
-        if start_time != 0 {
-            tokio::select! {
-                _ = tokio::time::sleep(Duration::from_secs(start_time as u64)) => {}
-
-                // Wait for process to terminate or crash before start_time
-                exit_status = child.wait() => {
-                    return Status::ErrorDuringStartup {
-                        exit_code: exit_status
-                            .expect("Failed to get exit status")
-                            .code()
-                            .expect("Failed to get exit code") as u8
-                    };
+        let ready = async {
+            match ready_timeout {
+                Some(ready_timeout) => {
+                    let ready_timeout = Duration::from_secs(ready_timeout as u64);
+                    tokio::time::timeout(ready_timeout, probe.ready())
+                        .await
+                        .is_ok()
+                }
+                None => {
+                    probe.ready().await;
+                    true
                 }
             }
+        };
+        let ready = tokio::select! {
+            // Readiness wins if the process became ready and exited at the same time
+            biased;
+
+            ready = ready => ready,
+
+            // Wait for process to terminate or crash before being ready
+            exit_status = Self::reap(child, pid_sender) => {
+                return Status::ErrorDuringStartup(exit_status.expect("Failed to get exit status"));
+            }
+        };
+        if !ready {
+            Self::kill_group(pid_sender);
+            return Status::ErrorDuringStartup(
+                Self::reap(child, pid_sender)
+                    .await
+                    .expect("Failed to get exit status"),
+            );
         }
 
+        ready_sender.send_replace(true);
         Self::send_new_status_to_task_manager(status_sender, Status::Running);
+        hooks.run(
+            HookEvent::Running,
+            HookDetails {
+                pid: child.id(),
+                ..Default::default()
+            },
+        );
         // Wait for process to terminate or crash
-        Status::Exited(child.wait().await.expect("error waiting for child"))
+        Status::Exited(
+            Self::reap(child, pid_sender)
+                .await
+                .expect("error waiting for child"),
+        )
+    }
+
+    /// Waits for the child to exit, then reaps it while clearing its pid, so that the pid is
+    /// never signaled once it can be reused. See `Handle::signal`.
+    async fn reap(
+        child: &mut Child,
+        pid_sender: &watch::Sender<Option<u32>>,
+    ) -> std::io::Result<ExitStatus> {
+        command::exited(child).await?;
+
+        let mut exit_status = None;
+        pid_sender.send_modify(|pid| {
+            *pid = None;
+            exit_status = Some(child.try_wait());
+        });
+        match exit_status {
+            Some(Ok(Some(exit_status))) => Ok(exit_status),
+            _ => child.wait().await,
+        }
     }
 
     /// Condition for restart:
-    /// - The programmed failed to start (i.e. it crashed before `config.start_time` seconds
-    ///   elapsed):
+    /// - The process was stopped by a kill command: returns false
+    ///
+    /// - A `restart` trigger fired: returns true, unless the process was not ready yet and we
+    ///   already attempted to start it `config.start_retries` times
+    ///
+    /// - The programmed failed to start (i.e. it could not be spawned, or it crashed before being
+    ///   ready according to `config.readiness`):
     ///   - We already attempted to start the program `config.start_retries` times (note that the
     ///     attempted start count is reset whenever the program starts successfully):
     ///     returns false (we don't want to retry)
@@ -320,8 +763,21 @@ impl Routine {
     ///   - `config.auto_restart` is `unexpected` and the exit status is in `config.exitcodes`: Return false (we don't want to restart)
     ///   - otherwise return true (we want to restart)
     ///
-    fn should_try_restart(&mut self, start_time: Instant, status: &Status) -> bool {
-        let started_properly = start_time.elapsed().as_secs() >= (*self.config.start_time()).into();
+    fn should_try_restart(&mut self, status: &Status) -> bool {
+        if self.stop_requested {
+            return false;
+        }
+
+        if self.restart_requested {
+            self.restart_requested = false;
+            // The attempts were reset if the process was ready
+            return self.start_attempts == 0 || self.start_attempts < *self.config.start_retries();
+        }
+
+        let started_properly = !matches!(
+            status,
+            Status::ErrorDuringStartup(_) | Status::FailedToSpawn(_)
+        );
 
         if started_properly {
             self.start_attempts = 0;
@@ -340,6 +796,37 @@ impl Routine {
         }
     }
 
+    /// Runs the hooks of the state a process ends up in once it is gone: `exited` if it was
+    /// running, otherwise `backoff` or `fatal` whether it will be retried or not.
+    fn run_exit_hooks(&self, status: &Status, will_restart: bool) {
+        let start_failure_event = if will_restart {
+            HookEvent::Backoff
+        } else {
+            HookEvent::Fatal
+        };
+        let (event, details) = match status {
+            Status::Exited(exit_status) => (
+                HookEvent::Exited,
+                HookDetails {
+                    exit_code: exit_status.code(),
+                    signal: exit_status.signal(),
+                    ..Default::default()
+                },
+            ),
+            Status::ErrorDuringStartup(exit_status) => (
+                start_failure_event,
+                HookDetails {
+                    exit_code: exit_status.code(),
+                    signal: exit_status.signal(),
+                    ..Default::default()
+                },
+            ),
+            Status::FailedToSpawn(_) => (start_failure_event, HookDetails::default()),
+            Status::Starting | Status::Running | Status::Unhealthy { .. } => return,
+        };
+        self.hooks.run(event, details);
+    }
+
     fn is_expected_status(&self, status: &Status) -> bool {
         if let Status::Exited(exit_status) = status {
             match exit_status.code() {
@@ -351,136 +838,38 @@ impl Routine {
         }
     }
 
-    fn send_new_status_to_task_manager(status_sender: &mut StatusSender, status: Status) {
-        status_sender.send(status).expect("Receiver was dropped");
+    fn send_new_status_to_task_manager(status_sender: &StatusSender, status: Status) {
+        status_sender.send(status);
     }
 
     /// Spawns the child and upgrades the start_attempts counter
-    async fn child_spawn(&mut self) -> Result<Child, Error> {
-        self.start_attempts += 1;
-        let child = self
-            .command
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
-        Ok(child)
-    }
-
-    ///  Listens to the outputs of a child process and logs them.
-    ///
-    ///  This function reads from both stdout and stderr streams of a child process,
-    ///  splitting the output by newlines and logging each line as it arrives.
-    ///
-    ///  The function uses `tokio::select!` to concurrently read from both streams,
-    ///  continuing until either stream is exhausted (read returns 0 bytes) or an
-    ///  error occurs. After the main loop exits, it flushes any remaining data that
-    ///  may not have been terminated by a newline character.
-    ///
-    ///  # Arguments
     ///
-    ///  * `outputs` - An `Outputs` struct containing the stdout and stderr handles
-    ///    from the child process.
-    ///
-    ///  # Panics
-    ///
-    ///  Will panic if the log sender has been dropped, which would indicate a
-    ///  critical failure in the channel communication.
-    async fn listen(
-        outputs: Outputs,
-        stdout_file: Arc<Mutex<OutputFile>>,
-        stderr_file: Arc<Mutex<OutputFile>>,
-        log_sender: LogSender,
-        program_name: String,
-    ) {
-        let stdout = outputs.stdout;
-        let stderr = outputs.stderr;
-
-        let mut stdout_file_mutex_guard = stdout_file.lock().await;
-        let mut stderr_file_mutex_guard = stderr_file.lock().await;
-
-        tokio::join!(
-            listen_and_log(
-                stdout,
-                log_sender.clone(),
-                &mut stdout_file_mutex_guard,
-                &program_name
-            ),
-            listen_and_log(
-                stderr,
-                log_sender,
-                &mut stderr_file_mutex_guard,
-                &program_name
-            ),
-        );
-    }
-}
-
-/// Sends a log message over the channel and writes it to the appropriate output file.
-/// This function performs two operations:
-/// - Write the log message to the corresponding output file (stdout or stderr)
-/// - Send the log message through the log channel to any receivers
-///
-/// # Arguments
-///
-/// * `log` - A `Log` struct containing the log type, the task's name and the log itself
-/// * `log_sender` - A `mpsc::Sender<Log>` to send log to the manager coroutine
-/// * `output` - A `OutputFile` enum that contains the file to write in
-///
-/// # Panics
-///
-/// Will panic if the `OutputFile` and the `LogType` enums are not accorded.
-/// That should never happen because those structs are both constructed side by side.
-///
-async fn dispatch_log(log: Log, log_sender: &mut LogSender, output: &mut OutputFile) {
-    match (output, &log.log_type) {
-        (OutputFile::Stdout(file), LogType::Stdout) => {
-            let _ = file.write_all(log.message.as_bytes()).await.inspect_err(|err| {
-                eprintln!("Taskmaster error: {}: Failed to write process stdout output to log file: {err}", log.program_name);
-            });
-        }
-        (OutputFile::Stderr(file), LogType::Stderr) => {
-            let _ = file.write_all(log.message.as_bytes()).await.inspect_err(|err| {
-                eprintln!("Taskmaster error: {}: Failed to write process stderr output to log file: {err}", log.program_name);
-            });
+    /// With `config.redirect_stderr`, stdout and stderr of the child are the same pipe, which
+    /// keeps the order in which the lines were written. The read end of that pipe is returned
+    /// alongside the child.
+    async fn child_spawn(&mut self) -> Result<(Child, Option<pipe::Receiver>), Error> {
+        self.start_attempts += 1;
+        self.command.stdin(Stdio::null());
+
+        if !*self.config.redirect_stderr() {
+            let child = self
+                .command
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?;
+            return Ok((child, None));
         }
-        _ => panic!(
-            "log function was called with different values for output and log_type, expected same values"
-        ),
-    }
-    let program_name = log.program_name.clone();
-    log_sender
-        .send(log)
-        .inspect_err(|_| {
-            eprintln!(
-                "Taskmaster error: {}: Log receiver was dropped",
-                program_name
-            )
-        })
-        .unwrap()
-}
 
-async fn listen_and_log<R: AsyncBufRead + Unpin>(
-    mut output: R,
-    mut sender: LogSender,
-    output_file: &mut OutputFile,
-    name: &str,
-) {
-    loop {
-        let mut buffer = Vec::new();
-        let bytes_read = output.read_until(b'\n', &mut buffer).await;
-
-        match bytes_read {
-            Ok(0) => break,
-            Ok(_) => {
-                let log = Log::new(output_file, &buffer, name);
-                dispatch_log(log, &mut sender, output_file).await;
-            }
-            Err(err) => {
-                eprintln!(
-                    "Taskmaster error: {name}: Error encountered while reading stderr: {err}"
-                );
-                break;
-            }
-        }
+        let (reader, writer) = std::io::pipe()?;
+        let child = self
+            .command
+            .stdout(writer.try_clone()?)
+            .stderr(writer)
+            .spawn();
+        // The command keeps its stdio until it is reconfigured, our copies of the write end must
+        // be closed for the read end to reach the end of the stream once the child exits
+        self.command.stdout(Stdio::null()).stderr(Stdio::null());
+
+        Ok((child?, Some(pipe::Receiver::from_owned_fd(reader.into())?)))
     }
 }
@@ -0,0 +1,58 @@
+use super::Log;
+use super::log_file::LogFile;
+use super::log_format::format_log;
+use super::pipe_sink::PipeSink;
+use super::syslog::SyslogSink;
+use crate::config::{Output, Program, program::LogFormat};
+use tokio::io;
+
+/// Destination of the lines captured from one output of a process.
+pub enum Sink {
+    File(LogFile),
+    Syslog(SyslogSink),
+    Pipe(PipeSink),
+}
+
+impl Sink {
+    /// Opens `output`, with the rotation settings used if it is a file.
+    pub async fn open(
+        output: &Output,
+        config: &Program,
+        max_bytes: u64,
+        backups: u32,
+    ) -> io::Result<Self> {
+        Ok(match output {
+            Output::File(path) => {
+                Self::File(LogFile::open(path, *config.append(), max_bytes, backups).await?)
+            }
+            Output::Syslog { socket } => Self::Syslog(SyslogSink::open(
+                socket,
+                *config.syslog_facility(),
+                config
+                    .syslog_tag()
+                    .clone()
+                    .unwrap_or_else(|| config.name().clone()),
+            )?),
+            Output::Pipe(command) => Self::Pipe(PipeSink::open(command)?),
+        })
+    }
+
+    /// Writes a captured line, in `format` for files and pipes. Syslog messages have their own
+    /// format.
+    pub async fn write(&mut self, log: &Log, format: LogFormat) -> io::Result<()> {
+        match self {
+            Self::File(file) => file.write(&format_log(log, format)).await,
+            Self::Syslog(syslog) => syslog.write(log).await,
+            Self::Pipe(pipe) => pipe.write(&format_log(log, format)).await,
+        }
+    }
+
+    pub async fn reopen(&mut self) -> io::Result<()> {
+        match self {
+            Self::File(file) => file.reopen().await,
+            Self::Syslog(syslog) => syslog.reopen(),
+            // Pipes have nothing to reopen
+            Self::Pipe(_) => Ok(()),
+        }
+    }
+}
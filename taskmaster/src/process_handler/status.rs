@@ -1,12 +1,20 @@
-use std::{fmt::Debug, process::ExitStatus};
+use commands::ProcessStatus;
+use std::{fmt::Debug, os::unix::process::ExitStatusExt, process::ExitStatus, sync::Arc};
 
 #[allow(dead_code)]
+#[derive(Clone)]
 pub enum Status {
     Starting,
     Running,
-    ErrorDuringStartup { exit_code: u8 },
-    FailedToSpawn(tokio::io::Error),
+    /// The process exited before it was ready, or was killed for not getting ready in time
+    ErrorDuringStartup(ExitStatus),
+    FailedToSpawn(Arc<tokio::io::Error>),
     Exited(ExitStatus),
+    /// Reported by an `unhealthy` trigger, the process keeps running
+    Unhealthy {
+        pattern: String,
+        line: String,
+    },
 }
 
 impl Debug for Status {
@@ -16,9 +24,33 @@ impl Debug for Status {
             Status::Running => write!(f, "Status::Running"),
             Status::Exited(_) => write!(f, "Status::Exited"),
             Status::FailedToSpawn(_) => write!(f, "Status::FailedToSpawn"),
-            Status::ErrorDuringStartup { exit_code } => {
-                write!(f, "Status::ErrorDuringStartup{{ exit_code = {exit_code} }}")
+            Status::Unhealthy { pattern, .. } => {
+                write!(f, "Status::Unhealthy{{ pattern = {pattern} }}")
             }
+            Status::ErrorDuringStartup(exit_status) => {
+                write!(f, "Status::ErrorDuringStartup({exit_status})")
+            }
+        }
+    }
+}
+
+impl From<Status> for ProcessStatus {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Starting => ProcessStatus::Starting,
+            Status::Running => ProcessStatus::Running,
+            Status::ErrorDuringStartup(exit_status) => ProcessStatus::ErrorDuringStartup {
+                exit_code: exit_status.code(),
+                signal: exit_status.signal(),
+            },
+            Status::FailedToSpawn(error) => ProcessStatus::FailedToSpawn {
+                error: error.to_string(),
+            },
+            Status::Exited(exit_status) => ProcessStatus::Exited {
+                exit_code: exit_status.code(),
+                signal: exit_status.signal(),
+            },
+            Status::Unhealthy { pattern, line } => ProcessStatus::Unhealthy { pattern, line },
         }
     }
 }
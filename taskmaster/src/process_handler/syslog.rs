@@ -0,0 +1,140 @@
+use super::log_format::trim_newline;
+use super::{Log, LogType};
+use crate::config::Facility;
+use chrono::SecondsFormat;
+use tokio::{io, net::UnixDatagram};
+
+const SEVERITY_ERROR: u8 = 3;
+const SEVERITY_INFO: u8 = 6;
+
+/// Sends every captured line as an RFC5424 datagram to the unix socket of a local syslog
+/// daemon. Lines from stderr are logged with the `err` severity, the others with `info`.
+pub struct SyslogSink {
+    socket: UnixDatagram,
+    path: String,
+    facility: Facility,
+    tag: String,
+}
+
+impl SyslogSink {
+    pub fn open(path: &str, facility: Facility, tag: String) -> io::Result<Self> {
+        Ok(Self {
+            socket: Self::connect(path)?,
+            path: path.to_string(),
+            facility,
+            tag,
+        })
+    }
+
+    fn connect(path: &str) -> io::Result<UnixDatagram> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(socket)
+    }
+
+    /// Sends the line, connecting to the socket again once if it fails, for when the syslog
+    /// daemon was restarted.
+    pub async fn write(&mut self, log: &Log) -> io::Result<()> {
+        let message = format_message(log, self.facility, &self.tag);
+        if self.socket.send(&message).await.is_err() {
+            self.reopen()?;
+            self.socket.send(&message).await?;
+        }
+        Ok(())
+    }
+
+    /// Connects to the socket again, for when the syslog daemon was restarted.
+    pub fn reopen(&mut self) -> io::Result<()> {
+        self.socket = Self::connect(&self.path)?;
+        Ok(())
+    }
+}
+
+/// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG`, leaving the hostname
+/// for the syslog daemon to fill in.
+fn format_message(log: &Log, facility: Facility, tag: &str) -> Vec<u8> {
+    let severity = match log.log_type {
+        LogType::Stderr => SEVERITY_ERROR,
+        LogType::Stdout | LogType::Merged => SEVERITY_INFO,
+    };
+    let pid = log
+        .pid
+        .map_or_else(|| "-".to_string(), |pid| pid.to_string());
+
+    let mut message = format!(
+        "<{}>1 {} - {tag} {pid} - - ",
+        facility.code() * 8 + severity,
+        log.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+    )
+    .into_bytes();
+    message.extend_from_slice(trim_newline(&log.message));
+    message
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn log(log_type: LogType) -> Log {
+        Log {
+            message: b"Hello taskmaster\n".to_vec(),
+            program_name: "nginx".to_string(),
+            instance: 0,
+            pid: Some(4242),
+            log_type,
+            timestamp: Utc.with_ymd_and_hms(2025, 3, 14, 15, 9, 26).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_format_message() {
+        assert_eq!(
+            format_message(&log(LogType::Stdout), Facility::Local0, "web"),
+            b"<134>1 2025-03-14T15:09:26.000Z - web 4242 - - Hello taskmaster"
+        );
+        assert_eq!(
+            format_message(&log(LogType::Stderr), Facility::User, "nginx"),
+            b"<11>1 2025-03-14T15:09:26.000Z - nginx 4242 - - Hello taskmaster"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send() {
+        let path = std::env::temp_dir().join("taskmaster_tests_syslog_send.sock");
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let mut sink =
+            SyslogSink::open(path.to_str().unwrap(), Facility::Daemon, "web".to_string()).unwrap();
+        sink.write(&log(LogType::Stdout)).await.unwrap();
+
+        let mut buffer = [0; 1024];
+        let len = server.recv(&mut buffer).await.unwrap();
+        assert!(buffer[..len].starts_with(b"<30>1 "));
+        assert!(buffer[..len].ends_with(b" - web 4242 - - Hello taskmaster"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reconnect() {
+        let path = std::env::temp_dir().join("taskmaster_tests_syslog_reconnect.sock");
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+        let mut sink =
+            SyslogSink::open(path.to_str().unwrap(), Facility::Daemon, "web".to_string()).unwrap();
+
+        // The syslog daemon is restarted
+        drop(server);
+        std::fs::remove_file(&path).unwrap();
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        sink.write(&log(LogType::Stdout)).await.unwrap();
+        let mut buffer = [0; 1024];
+        let len = server.recv(&mut buffer).await.unwrap();
+        assert!(buffer[..len].ends_with(b" - web 4242 - - Hello taskmaster"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
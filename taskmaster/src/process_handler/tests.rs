@@ -1,9 +1,11 @@
-use crate::process_handler::{Log, LogType, Routine, Status};
+use crate::process_handler::routine::{
+    KillCommand, KillCommandSender, LogReceiver, ProcessState, StatusReceiver,
+};
+use crate::process_handler::{Handle, Log, LogType, Routine, Status};
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use tokio::sync::{Mutex, mpsc::UnboundedReceiver};
+use tokio::sync::{Mutex, oneshot};
 
-async fn check_status(status_receiver: Arc<Mutex<UnboundedReceiver<Status>>>) {
+async fn check_status(status_receiver: Arc<Mutex<StatusReceiver>>) {
     match status_receiver.lock().await.recv().await.unwrap() {
         Status::Starting => {}
         other => panic!("Expected Status::Starting, got {other:?}"),
@@ -14,27 +16,25 @@ async fn check_status(status_receiver: Arc<Mutex<UnboundedReceiver<Status>>>) {
     }
 }
 
-async fn check_status_exited(status_receiver: Arc<Mutex<UnboundedReceiver<Status>>>) {
+async fn check_status_exited(status_receiver: Arc<Mutex<StatusReceiver>>) {
     match status_receiver.lock().await.recv().await.unwrap() {
         Status::Exited(_) => {}
         status => panic!("not expected {status:?}"),
     }
 }
 
-async fn check_realtime_output(mut log_receiver: mpsc::UnboundedReceiver<Log>) {
-    loop {
-        match log_receiver.recv().await {
-            Some(log) => match log.log_type {
-                LogType::Stdout => {
-                    assert_eq!(log.message, "Hello taskmaster!\n");
-                    assert_eq!(log.program_name, "taskmaster_test_task");
-                }
-                LogType::Stderr => {
-                    assert_eq!(log.message, "");
-                    assert_eq!(log.program_name, "taskmaster_test_task");
-                }
-            },
-            None => break,
+async fn check_realtime_output(mut log_receiver: LogReceiver) {
+    while let Some(log) = log_receiver.recv().await {
+        match log.log_type {
+            LogType::Stdout => {
+                assert_eq!(log.message, b"Hello taskmaster!\n");
+                assert_eq!(log.program_name, "taskmaster_test_task");
+            }
+            LogType::Stderr => {
+                assert!(log.message.is_empty());
+                assert_eq!(log.program_name, "taskmaster_test_task");
+            }
+            LogType::Merged => panic!("stderr is not redirected to stdout"),
         }
     }
 }
@@ -46,10 +46,7 @@ async fn create_task() {
         io::{Cursor, Read},
     };
 
-    use tokio::{
-        fs::remove_file,
-        sync::{Mutex, mpsc::UnboundedReceiver},
-    };
+    use tokio::{fs::remove_file, sync::Mutex};
 
     use crate::config::Config;
 
@@ -80,11 +77,11 @@ async fn create_task() {
         .next()
         .expect("Config vector is empty");
 
-    let routine_handle = Routine::spawn(config)
+    let routine_handle = Routine::spawn(config, 0)
         .await
         .expect("failed to spawn tokio::task");
     let log_checker_handle = tokio::spawn(check_realtime_output(routine_handle.log_receiver));
-    let status_receiver: Arc<Mutex<UnboundedReceiver<Status>>> =
+    let status_receiver: Arc<Mutex<StatusReceiver>> =
         Arc::new(Mutex::new(routine_handle.status_receiver));
     let status_checker_handle = tokio::spawn(check_status(Arc::clone(&status_receiver)));
 
@@ -139,7 +136,7 @@ async fn create_task_then_interrupt() {
 
     let yaml_content = r#"programs:
     taskmaster_test_task:
-        cmd: "cat"
+        cmd: "sleep 30"
         numprocs: 1
         umask: 022
         workingdir: /tmp
@@ -164,16 +161,20 @@ async fn create_task_then_interrupt() {
         .next()
         .expect("Config vector is empty");
 
-    let routine_handle = Routine::spawn(config)
+    let routine_handle = Routine::spawn(config, 0)
         .await
         .expect("failed to spawn tokio::task");
-    let status_receiver: Arc<Mutex<UnboundedReceiver<Status>>> =
+    let status_receiver: Arc<Mutex<StatusReceiver>> =
         Arc::new(Mutex::new(routine_handle.status_receiver));
     let handle2 = tokio::spawn(check_status(Arc::clone(&status_receiver)));
 
     handle2.await.expect("failed to join status handle"); // wait for running status to send stop signal
     let (s, r) = oneshot::channel();
-    if let Err(e) = routine_handle.kill_command_sender.send(s).await {
+    if let Err(e) = routine_handle
+        .kill_command_sender
+        .send(KillCommand::new(s))
+        .await
+    {
         panic!("Failed to send stop signal: {:?}", e);
     }
     r.await.expect("error receiving process state");
@@ -211,3 +212,773 @@ async fn create_task_then_interrupt() {
         .inspect_err(|err| eprintln!("{err}"))
         .unwrap();
 }
+
+fn program_from_yaml(yaml_content: &str) -> crate::config::Program {
+    use crate::config::Config;
+    use std::io::Cursor;
+
+    Config::from_reader(Cursor::new(yaml_content))
+        .expect("Parse error")
+        .programs
+        .into_iter()
+        .next()
+        .expect("Config vector is empty")
+}
+
+async fn stop_routine(
+    kill_command_sender: KillCommandSender,
+    join_handle: tokio::task::JoinHandle<()>,
+) {
+    let (s, r) = tokio::sync::oneshot::channel();
+    kill_command_sender
+        .send(KillCommand::new(s))
+        .await
+        .expect("Failed to send stop signal");
+    r.await.expect("error receiving process state");
+    join_handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn readiness_regex() {
+    let config = program_from_yaml(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"echo booting; sleep 0.2; echo ready to serve; sleep 30\""
+        readiness:
+            type: regex
+            pattern: "^ready""#,
+    );
+
+    let Handle {
+        join_handle,
+        status_receiver,
+        kill_command_sender,
+        ..
+    } = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let status_receiver = Arc::new(Mutex::new(status_receiver));
+    check_status(Arc::clone(&status_receiver)).await;
+
+    stop_routine(kill_command_sender, join_handle).await;
+    check_status_exited(status_receiver).await;
+}
+
+#[tokio::test]
+async fn readiness_regex_exit_before_match() {
+    let config = program_from_yaml(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "echo booting"
+        readiness:
+            type: regex
+            pattern: "^ready""#,
+    );
+
+    let mut routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    routine_handle.join_handle.await.unwrap();
+
+    match routine_handle.status_receiver.recv().await.unwrap() {
+        Status::Starting => {}
+        other => panic!("Expected Status::Starting, got {other:?}"),
+    }
+    match routine_handle.status_receiver.recv().await.unwrap() {
+        Status::ErrorDuringStartup(exit_status) if exit_status.code() == Some(0) => {}
+        other => panic!("Expected Status::ErrorDuringStartup, got {other:?}"),
+    }
+}
+
+/// Runs a program that never gets ready and returns the status it ends up with
+async fn error_during_startup(cmd: &str) -> std::process::ExitStatus {
+    let config = program_from_yaml(&format!(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "{cmd}"
+        readiness:
+            type: regex
+            pattern: "^ready"
+            timeout: 1"#
+    ));
+
+    let mut routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        routine_handle.join_handle,
+    )
+    .await
+    .expect("the process was not given up on")
+    .unwrap();
+
+    match routine_handle.status_receiver.recv().await.unwrap() {
+        Status::Starting => {}
+        other => panic!("Expected Status::Starting, got {other:?}"),
+    }
+    match routine_handle.status_receiver.recv().await.unwrap() {
+        Status::ErrorDuringStartup(exit_status) => exit_status,
+        other => panic!("Expected Status::ErrorDuringStartup, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn readiness_timeout() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let exit_status = error_during_startup("sleep 30").await;
+    assert_eq!(exit_status.signal(), Some(signal::Signal::SIGKILL as i32));
+}
+
+#[tokio::test]
+async fn readiness_killed_before_ready() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let exit_status = error_during_startup("sh -c 'kill -TERM $$'").await;
+    assert_eq!(exit_status.signal(), Some(signal::Signal::SIGTERM as i32));
+}
+
+#[tokio::test]
+async fn readiness_tcp() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let config = program_from_yaml(&format!(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "sleep 30"
+        readiness:
+            type: tcp
+            address: "{addr}""#
+    ));
+
+    let Handle {
+        join_handle,
+        status_receiver,
+        kill_command_sender,
+        ..
+    } = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let status_receiver = Arc::new(Mutex::new(status_receiver));
+    check_status(Arc::clone(&status_receiver)).await;
+
+    stop_routine(kill_command_sender, join_handle).await;
+    check_status_exited(status_receiver).await;
+}
+
+#[tokio::test]
+async fn log_format_json() {
+    let stdout_file = "/tmp/taskmaster_tests_log_format_json.stdout";
+    let config = program_from_yaml(&format!(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "echo Hello taskmaster!"
+        stdout: {stdout_file}
+        log_format: json"#
+    ));
+
+    let routine_handle = Routine::spawn(config, 3)
+        .await
+        .expect("failed to spawn tokio::task");
+    routine_handle.join_handle.await.unwrap();
+
+    let content = std::fs::read_to_string(stdout_file).expect("failed to read stdout file");
+    let line: serde_json::Value = serde_json::from_str(&content).expect("invalid json line");
+    assert_eq!(line["program"], "taskmaster_test_task");
+    assert_eq!(line["instance"], 3);
+    assert_eq!(line["stream"], "stdout");
+    assert_eq!(line["message"], "Hello taskmaster!");
+    assert!(line["pid"].is_u64());
+    assert!(line["timestamp"].is_string());
+
+    std::fs::remove_file(stdout_file).unwrap();
+}
+
+#[tokio::test]
+async fn shared_output_files() {
+    let stdout_file = "/tmp/taskmaster_tests_shared_output_files.stdout";
+    let config = program_from_yaml(&format!(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "echo Hello taskmaster!"
+        stdout: {stdout_file}
+        numprocs: 2"#
+    ));
+
+    let first = Routine::spawn(config.clone(), 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let second = Routine::spawn_with_output_files(config, 1, first.output_files.clone());
+    first.join_handle.await.unwrap();
+    second.join_handle.await.unwrap();
+
+    // The second instance neither truncated the file nor lost its line
+    let content = std::fs::read_to_string(stdout_file).expect("failed to read stdout file");
+    assert_eq!(content, "Hello taskmaster!\nHello taskmaster!\n");
+
+    std::fs::remove_file(stdout_file).unwrap();
+}
+
+#[tokio::test]
+async fn redirect_stderr() {
+    let stdout_file = "/tmp/taskmaster_tests_redirect_stderr.stdout";
+    let stderr_file = "/tmp/taskmaster_tests_redirect_stderr.stderr";
+    let _ = std::fs::remove_file(stderr_file);
+    let config = program_from_yaml(&format!(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"echo out 1; echo err 1 >&2; echo out 2; echo err 2 >&2\""
+        stdout: {stdout_file}
+        stderr: {stderr_file}
+        redirect_stderr: true"#
+    ));
+
+    let mut routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    routine_handle.join_handle.await.unwrap();
+
+    let mut messages = Vec::new();
+    while let Some(log) = routine_handle.log_receiver.try_recv() {
+        assert!(matches!(log.log_type, LogType::Merged));
+        messages.push(String::from_utf8(log.message).unwrap());
+    }
+    assert_eq!(messages, ["out 1\n", "err 1\n", "out 2\n", "err 2\n"]);
+
+    let content = std::fs::read_to_string(stdout_file).expect("failed to read stdout file");
+    assert_eq!(content, "out 1\nerr 1\nout 2\nerr 2\n");
+    assert!(!std::path::Path::new(stderr_file).exists());
+
+    std::fs::remove_file(stdout_file).unwrap();
+}
+
+#[tokio::test]
+async fn log_buffer_without_output_files() {
+    let config = program_from_yaml(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"echo line 1; sleep 0.1; echo line 2 >&2; sleep 0.1; echo line 3\""
+        log_buffer_lines: 2"#,
+    );
+
+    let routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let Handle {
+        join_handle,
+        log_buffer,
+        ..
+    } = routine_handle;
+    join_handle.await.unwrap();
+
+    let messages =
+        |logs: Vec<Log>| -> Vec<Vec<u8>> { logs.into_iter().map(|log| log.message).collect() };
+    assert_eq!(
+        messages(log_buffer.tail(1, Some(&LogType::Stderr))),
+        [b"line 2\n"]
+    );
+    assert_eq!(
+        messages(log_buffer.tail(10, Some(&LogType::Stdout))).len(),
+        1
+    );
+    assert_eq!(messages(log_buffer.tail(10, None)).len(), 2);
+}
+
+#[tokio::test]
+async fn without_consumers() {
+    let config = program_from_yaml(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"for i in $(seq 5000); do echo line $i; done\"""#,
+    );
+
+    let Handle {
+        join_handle,
+        log_buffer,
+        ..
+    } = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    join_handle
+        .await
+        .expect("routine panicked without consumers");
+
+    let last_logs = log_buffer.tail(1, None);
+    assert_eq!(last_logs[0].message, b"line 5000\n");
+}
+
+#[tokio::test]
+async fn binary_output_and_long_lines() {
+    let stdout_file = "/tmp/taskmaster_tests_binary_output.stdout";
+    let config = program_from_yaml(&format!(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "printf '\\377\\000binary\\n0123456789ab\\n'"
+        stdout: {stdout_file}
+        max_line_length: 8"#
+    ));
+
+    let mut routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    routine_handle.join_handle.await.unwrap();
+
+    let mut messages = Vec::new();
+    while let Some(log) = routine_handle.log_receiver.try_recv() {
+        messages.push(log.message);
+    }
+    assert_eq!(
+        messages,
+        [&b"\xff\x00binary"[..], b"\n", b"01234567", b"89ab\n"]
+    );
+
+    let content = std::fs::read(stdout_file).expect("failed to read stdout file");
+    assert_eq!(content, b"\xff\x00binary\n0123456789ab\n");
+
+    std::fs::remove_file(stdout_file).unwrap();
+}
+
+#[tokio::test]
+async fn syslog_output() {
+    let socket_path = std::env::temp_dir().join("taskmaster_tests_syslog_output.sock");
+    let _ = std::fs::remove_file(&socket_path);
+    let server = tokio::net::UnixDatagram::bind(&socket_path).unwrap();
+    let config = program_from_yaml(&format!(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"echo out; sleep 0.1; echo err >&2\""
+        stdout: "syslog:{0}"
+        stderr: "syslog:{0}"
+        syslog_facility: local1
+        syslog_tag: web"#,
+        socket_path.display()
+    ));
+
+    let routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    routine_handle.join_handle.await.unwrap();
+
+    let mut buffer = [0; 1024];
+    for (priority, message) in [("<142>1 ", "out"), ("<139>1 ", "err")] {
+        let len = server.recv(&mut buffer).await.unwrap();
+        let datagram = String::from_utf8_lossy(&buffer[..len]);
+        assert!(datagram.starts_with(priority), "{datagram}");
+        assert!(datagram.ends_with(&format!(" - - {message}")), "{datagram}");
+        assert!(datagram.contains(" - web "), "{datagram}");
+    }
+
+    std::fs::remove_file(&socket_path).unwrap();
+}
+
+#[tokio::test]
+async fn pipe_output() {
+    let output_file = "/tmp/taskmaster_tests_pipe_output.log";
+    let _ = std::fs::remove_file(output_file);
+    let config = program_from_yaml(&format!(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"echo line 1; echo line 2\""
+        stdout: "|sh -c 'tr a-z A-Z > {output_file}'""#
+    ));
+
+    let routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let Handle {
+        join_handle,
+        output_files,
+        ..
+    } = routine_handle;
+    join_handle.await.unwrap();
+    // Closes the stdin of the sink process
+    drop(output_files);
+
+    let mut content = String::new();
+    for _ in 0..50 {
+        content = std::fs::read_to_string(output_file).unwrap_or_default();
+        if content.len() == 14 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(content, "LINE 1\nLINE 2\n");
+
+    std::fs::remove_file(output_file).unwrap();
+}
+
+#[tokio::test]
+async fn trigger_restart() {
+    let config = program_from_yaml(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"echo up; sleep 0.1; echo connection pool exhausted; exec sleep 30\""
+        triggers:
+          - pattern: "pool exhausted$"
+            action: restart"#,
+    );
+
+    let Handle {
+        join_handle,
+        status_receiver,
+        kill_command_sender,
+        ..
+    } = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let status_receiver = Arc::new(Mutex::new(status_receiver));
+    check_status(Arc::clone(&status_receiver)).await;
+    check_status_exited(Arc::clone(&status_receiver)).await;
+    // Restarted even though autorestart is false
+    check_status(Arc::clone(&status_receiver)).await;
+    // The trigger firing again right away is ignored
+    let next_status = tokio::time::timeout(std::time::Duration::from_millis(500), async {
+        status_receiver.lock().await.recv().await
+    })
+    .await;
+    assert!(next_status.is_err(), "{next_status:?}");
+
+    stop_routine(kill_command_sender, join_handle).await;
+}
+
+#[tokio::test]
+async fn trigger_restart_wedged() {
+    let config = program_from_yaml(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"trap '' TERM; echo pool exhausted; exec sleep 30\""
+        stopsignal: TERM
+        stoptime: 1
+        triggers:
+          - pattern: "pool exhausted$"
+            action: restart"#,
+    );
+
+    let Handle {
+        join_handle,
+        status_receiver,
+        kill_command_sender,
+        ..
+    } = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let status_receiver = Arc::new(Mutex::new(status_receiver));
+    // Killed once `stoptime` is over, since it ignores its stop signal
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        check_status(Arc::clone(&status_receiver)).await;
+        check_status_exited(Arc::clone(&status_receiver)).await;
+        check_status(Arc::clone(&status_receiver)).await;
+    })
+    .await
+    .expect("the wedged process was not restarted");
+
+    stop_routine(kill_command_sender, join_handle).await;
+}
+
+#[tokio::test]
+async fn trigger_restart_before_ready() {
+    let config = program_from_yaml(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"echo connection pool exhausted; exec sleep 30\""
+        starttime: 5
+        startretries: 0
+        triggers:
+          - pattern: "pool exhausted$"
+            action: restart"#,
+    );
+
+    let routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    // Restarting before the process is ready uses up a start attempt, with none left to retry
+    tokio::time::timeout(
+        std::time::Duration::from_secs(3),
+        routine_handle.join_handle,
+    )
+    .await
+    .expect("the process was restarted")
+    .unwrap();
+}
+
+#[tokio::test]
+async fn trigger_signal_and_event() {
+    let config = program_from_yaml(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"trap 'echo got hup' HUP; echo reload please; while true; do sleep 0.05; done\""
+        stopsignal: TERM
+        triggers:
+          - pattern: "^reload please$"
+            action: signal
+            signal: HUP
+          - pattern: "^got (.*)$"
+            action: event"#,
+    );
+
+    let routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let mut events = routine_handle.subscribe_events().unwrap();
+
+    let event = events.recv().await.unwrap();
+    assert_eq!(event.program_name, "taskmaster_test_task");
+    assert_eq!(event.pattern, "^got (.*)$");
+    assert_eq!(event.line, "got hup");
+
+    stop_routine(
+        routine_handle.kill_command_sender,
+        routine_handle.join_handle,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn trigger_unhealthy() {
+    let config = program_from_yaml(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"echo slow query detected; exec sleep 30\""
+        triggers:
+          - pattern: "slow query"
+            action: unhealthy"#,
+    );
+
+    let Handle {
+        join_handle,
+        status_receiver,
+        kill_command_sender,
+        ..
+    } = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let status_receiver = Arc::new(Mutex::new(status_receiver));
+    check_status(Arc::clone(&status_receiver)).await;
+    match status_receiver.lock().await.recv().await.unwrap() {
+        Status::Unhealthy { pattern, line } => {
+            assert_eq!(pattern, "slow query");
+            assert_eq!(line, "slow query detected");
+        }
+        status => panic!("not expected {status:?}"),
+    }
+
+    stop_routine(kill_command_sender, join_handle).await;
+    check_status_exited(status_receiver).await;
+}
+
+#[tokio::test]
+async fn hooks_on_state_transitions() {
+    use std::time::Duration;
+
+    let hook_output = "/tmp/taskmaster_tests_hooks";
+    let _ = std::fs::remove_file(hook_output);
+    let config = program_from_yaml(&format!(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"exit 3\""
+        hooks:
+          - events: [started]
+            cmd: "sleep 30"
+            timeout: 30
+          - events: [exited]
+            cmd: "bash -c 'echo $TASKMASTER_EVENT $TASKMASTER_PROGRAM $TASKMASTER_EXIT_CODE $(cat) > {hook_output}.part && mv {hook_output}.part {hook_output}'""#
+    ));
+
+    let routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    // A hook that is still running does not hold the routine back
+    tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        routine_handle.join_handle,
+    )
+    .await
+    .expect("the routine waited for its hooks")
+    .unwrap();
+
+    let output = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            match std::fs::read_to_string(hook_output) {
+                Ok(output) => break output,
+                Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        }
+    })
+    .await
+    .expect("the exited hook did not run");
+    std::fs::remove_file(hook_output).unwrap();
+
+    assert!(
+        output.starts_with("exited taskmaster_test_task 3 {"),
+        "{output}"
+    );
+    assert!(output.contains(r#""event":"exited""#), "{output}");
+    assert!(output.contains(r#""instance":0"#), "{output}");
+    assert!(output.contains(r#""exit_code":3"#), "{output}");
+}
+
+#[tokio::test]
+async fn current_pid() {
+    let config = program_from_yaml(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "sleep 30""#,
+    );
+
+    let routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let status_receiver = Arc::new(Mutex::new(routine_handle.subscribe_statuses().unwrap()));
+    check_status(Arc::clone(&status_receiver)).await;
+    let pid = routine_handle.current_pid().expect("the process is alive");
+    assert_eq!(unsafe { libc::signal::kill(pid as i32, 0) }, 0);
+
+    let pid_receiver = routine_handle.pid.clone();
+    stop_routine(
+        routine_handle.kill_command_sender,
+        routine_handle.join_handle,
+    )
+    .await;
+    assert_eq!(*pid_receiver.borrow(), None);
+}
+
+async fn stop_ignoring_process(
+    stoptime: u32,
+    kill_command: impl FnOnce(oneshot::Sender<ProcessState>) -> KillCommand,
+) -> std::time::Duration {
+    use std::os::unix::process::ExitStatusExt;
+
+    let config = program_from_yaml(&format!(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"trap '' INT; echo ready; while true; do sleep 0.05; done\""
+        stoptime: {stoptime}
+        readiness:
+            type: regex
+            pattern: "^ready$""#
+    ));
+
+    let routine_handle = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let status_receiver = Arc::new(Mutex::new(routine_handle.status_receiver));
+    check_status(Arc::clone(&status_receiver)).await;
+
+    let start = std::time::Instant::now();
+    let (s, r) = oneshot::channel();
+    routine_handle
+        .kill_command_sender
+        .send(kill_command(s))
+        .await
+        .expect("Failed to send stop signal");
+    r.await.expect("error receiving process state");
+    routine_handle.join_handle.await.unwrap();
+
+    match status_receiver.lock().await.recv().await.unwrap() {
+        Status::Exited(exit_status) => assert_eq!(exit_status.signal(), Some(9)),
+        status => panic!("not expected {status:?}"),
+    }
+    start.elapsed()
+}
+
+#[tokio::test]
+async fn stop_timeout_kills_the_process() {
+    let elapsed = stop_ignoring_process(30, |sender| KillCommand {
+        timeout: Some(1),
+        ..KillCommand::new(sender)
+    })
+    .await;
+    assert!(elapsed >= std::time::Duration::from_secs(1), "{elapsed:?}");
+    assert!(elapsed < std::time::Duration::from_secs(5), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn forced_stop() {
+    let elapsed = stop_ignoring_process(30, |sender| KillCommand {
+        force: true,
+        ..KillCommand::new(sender)
+    })
+    .await;
+    assert!(elapsed < std::time::Duration::from_secs(1), "{elapsed:?}");
+}
+
+/// Stops a process whose child keeps its output open, and checks that the child is gone too
+async fn stop_process_group(
+    stoptime: u32,
+    kill_command: impl FnOnce(oneshot::Sender<ProcessState>) -> KillCommand,
+) -> std::time::Duration {
+    let config = program_from_yaml(&format!(
+        r#"programs:
+    taskmaster_test_task:
+        cmd: "bash -c \"trap '' INT; sleep 30 & echo ready $!; wait\""
+        stoptime: {stoptime}
+        readiness:
+            type: regex
+            pattern: "^ready""#
+    ));
+
+    let Handle {
+        join_handle,
+        status_receiver,
+        mut log_receiver,
+        kill_command_sender,
+        ..
+    } = Routine::spawn(config, 0)
+        .await
+        .expect("failed to spawn tokio::task");
+    let status_receiver = Arc::new(Mutex::new(status_receiver));
+    check_status(Arc::clone(&status_receiver)).await;
+    let log = log_receiver.recv().await.unwrap();
+    let sleep_pid = String::from_utf8(log.message).unwrap()["ready ".len()..]
+        .trim()
+        .to_string();
+
+    // The output of the process stays open as long as one of its children is alive
+    let start = std::time::Instant::now();
+    let (s, r) = oneshot::channel();
+    kill_command_sender
+        .send(kill_command(s))
+        .await
+        .expect("Failed to send stop signal");
+    r.await.expect("error receiving process state");
+    join_handle.await.unwrap();
+    let elapsed = start.elapsed();
+
+    // Gone, or left as a zombie until its new parent reaps it. It closes its files before it
+    // shows up as a zombie.
+    let mut stat = String::new();
+    for _ in 0..50 {
+        stat = std::fs::read_to_string(format!("/proc/{sleep_pid}/stat")).unwrap_or_default();
+        let state = stat.rsplit_once(") ").map(|(_, stat)| &stat[..1]);
+        if matches!(state, None | Some("Z")) {
+            return elapsed;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("{stat}")
+}
+
+#[tokio::test]
+async fn stop_timeout_kills_the_process_group() {
+    let elapsed = stop_process_group(1, KillCommand::new).await;
+    assert!(elapsed >= std::time::Duration::from_secs(1), "{elapsed:?}");
+    assert!(elapsed < std::time::Duration::from_secs(5), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn forced_stop_kills_the_process_group() {
+    let elapsed = stop_process_group(30, |sender| KillCommand {
+        force: true,
+        ..KillCommand::new(sender)
+    })
+    .await;
+    assert!(elapsed < std::time::Duration::from_secs(1), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn zero_stop_time_kills_the_process_group() {
+    let elapsed = stop_process_group(0, KillCommand::new).await;
+    assert!(elapsed < std::time::Duration::from_secs(1), "{elapsed:?}");
+}
@@ -0,0 +1,121 @@
+use super::log_format::trim_newline;
+use crate::config::program::{Trigger, TriggerAction};
+use commands::Event;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+/// Number of fired triggers waiting for the routine, further ones are dropped.
+const FIRED_TRIGGER_CHANNEL_CAPACITY: usize = 16;
+
+/// Sent to the clients subscribed to the events of a program when a trigger with the `event`
+/// action fires.
+#[derive(Debug, Clone)]
+pub struct TriggerEvent {
+    pub program_name: String,
+    pub instance: u32,
+    pub pattern: String,
+    pub line: String,
+}
+
+impl From<TriggerEvent> for Event {
+    fn from(event: TriggerEvent) -> Self {
+        Event::Trigger {
+            program: event.program_name,
+            instance: event.instance,
+            pattern: event.pattern,
+            line: event.line,
+        }
+    }
+}
+
+/// A trigger whose pattern matched a line of output, for the routine to act upon.
+#[derive(Debug)]
+pub(super) struct FiredTrigger {
+    pub action: TriggerAction,
+    pub pattern: String,
+    pub line: String,
+}
+
+pub(super) type FiredTriggerReceiver = mpsc::Receiver<FiredTrigger>;
+
+/// Checks the lines captured from the process output against the triggers of its program.
+pub(super) struct TriggerMatcher {
+    program_name: String,
+    triggers: Vec<Trigger>,
+    sender: mpsc::Sender<FiredTrigger>,
+}
+
+impl TriggerMatcher {
+    pub(super) fn new(
+        program_name: String,
+        triggers: Vec<Trigger>,
+    ) -> (Self, FiredTriggerReceiver) {
+        let (sender, receiver) = mpsc::channel(FIRED_TRIGGER_CHANNEL_CAPACITY);
+        (
+            Self {
+                program_name,
+                triggers,
+                sender,
+            },
+            receiver,
+        )
+    }
+
+    pub(super) fn check(&self, line: &[u8]) {
+        if self.triggers.is_empty() {
+            return;
+        }
+
+        // Patterns are matched against the line without its newline, so that `$` anchors work
+        let line = String::from_utf8_lossy(trim_newline(line));
+        for trigger in self.triggers.iter() {
+            if trigger.pattern.is_match(&line) {
+                let fired = FiredTrigger {
+                    action: trigger.action.clone(),
+                    pattern: trigger.pattern.as_str().to_string(),
+                    line: line.to_string(),
+                };
+                // The routine is busy with the previous ones when the channel is full
+                if let Err(TrySendError::Full(fired)) = self.sender.try_send(fired) {
+                    eprintln!(
+                        "Taskmaster error: {}: Trigger `{}` dropped, too many triggers fired at once",
+                        self.program_name, fired.pattern
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Pattern;
+
+    #[test]
+    fn test_check() {
+        let (matcher, mut receiver) = TriggerMatcher::new(
+            "web".to_string(),
+            vec![
+                Trigger {
+                    pattern: Pattern::try_from("exhausted$").unwrap(),
+                    action: TriggerAction::Restart,
+                },
+                Trigger {
+                    pattern: Pattern::try_from("pool").unwrap(),
+                    action: TriggerAction::Event,
+                },
+            ],
+        );
+
+        matcher.check(b"all good\n");
+        assert!(receiver.try_recv().is_err());
+
+        matcher.check(b"connection pool exhausted\n");
+        let fired = receiver.try_recv().unwrap();
+        assert_eq!(fired.action, TriggerAction::Restart);
+        assert_eq!(fired.line, "connection pool exhausted");
+        let fired = receiver.try_recv().unwrap();
+        assert_eq!(fired.action, TriggerAction::Event);
+        assert_eq!(fired.pattern, "pool");
+    }
+}
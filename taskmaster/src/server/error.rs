@@ -1,4 +1,4 @@
-use std::{fmt::Display, io};
+use std::{fmt::Display, io, path::PathBuf};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -6,6 +6,25 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub enum Error {
     #[allow(dead_code)]
     BindTcpListener { addr: String, error: io::Error },
+    #[allow(dead_code)]
+    BindUnixListener { path: PathBuf, error: io::Error },
+    #[allow(dead_code)]
+    ReadSecret { path: PathBuf, error: io::Error },
+    #[allow(dead_code)]
+    LoadPem {
+        path: PathBuf,
+        error: rustls_pki_types::pem::Error,
+    },
+    #[allow(dead_code)]
+    Tls(tokio_rustls::rustls::Error),
+    #[allow(dead_code)]
+    ClientVerifier(tokio_rustls::rustls::server::VerifierBuilderError),
+    #[allow(dead_code)]
+    UnknownUser(String),
+    #[allow(dead_code)]
+    UnknownGroup(String),
+    /// Neither the tcp nor the unix socket listener is enabled
+    NoListener,
 }
 
 impl Display for Error {
@@ -2,46 +2,180 @@ mod error;
 pub use error::Error;
 use error::Result;
 
+mod tls;
+
+mod unix_socket;
+use unix_socket::UnixSocketListener;
+
 use crate::{
     Program,
     client_handler::{ClientHandler, ClientId},
+    config::ServerConfig,
     tasks_manager,
 };
-use std::os::fd::AsRawFd;
-use tokio::net::{TcpListener, ToSocketAddrs};
+use commands::Role;
+use std::{io, os::fd::AsRawFd, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream, UnixStream},
+};
+use tokio_rustls::TlsAcceptor;
+
+/// Time a tcp client has to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time waited after failing to accept a client, before accepting the next ones
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
 
 pub struct Server {
     tasks_manager: tasks_manager::Handle,
-    listener: TcpListener,
+    tcp_listener: Option<TcpListener>,
+    unix_listener: Option<UnixSocketListener>,
+    /// Secret the tcp clients authenticate with, if they have to
+    secret: Option<Arc<[u8]>>,
+    /// Wraps the tcp connections when TLS is enabled
+    tls_acceptor: Option<TlsAcceptor>,
 }
 
 impl Server {
-    pub async fn new(tasks: Vec<Program>, addr: impl ToSocketAddrs + Into<String>) -> Result<Self> {
+    /// Listens on `{address}:{port}` and on the unix socket, as enabled by `config`.
+    pub async fn new(tasks: Vec<Program>, port: i32, config: &ServerConfig) -> Result<Self> {
+        if !config.tcp && config.unix_socket.is_none() {
+            return Err(Error::NoListener);
+        }
+
+        let secret = match &config.auth {
+            Some(auth) => Some(commands::auth::read_secret(&auth.secret_file).map_err(
+                |error| Error::ReadSecret {
+                    path: auth.secret_file.clone(),
+                    error,
+                },
+            )?),
+            None => None,
+        };
+
+        let tcp_listener = match config.tcp.then(|| tcp_address(&config.address, port)) {
+            Some(addr) => Some(
+                TcpListener::bind(&addr)
+                    .await
+                    .map_err(|error| Error::BindTcpListener { addr, error })?,
+            ),
+            None => None,
+        };
+        let unix_listener = config
+            .unix_socket
+            .as_ref()
+            .map(UnixSocketListener::bind)
+            .transpose()?;
+
+        let tls_acceptor = config.tls.as_ref().map(tls::acceptor).transpose()?;
+
         let tasks_manager = tasks_manager::spawn(tasks).await;
 
         Ok(Self {
             tasks_manager,
-            listener: TcpListener::bind(&addr)
-                .await
-                .map_err(|error| Error::BindTcpListener {
-                    addr: addr.into(),
-                    error,
-                })?,
+            tcp_listener,
+            unix_listener,
+            secret: secret.map(Arc::from),
+            tls_acceptor,
         })
     }
 
     pub async fn run(self) {
         loop {
-            let (socket, _) = self.listener.accept().await.unwrap();
+            tokio::select! {
+                socket = accept_tcp(&self.tcp_listener) => match socket {
+                    Ok(socket) => self.spawn_tcp_client(socket),
+                    Err(err) => accept_failed("tcp", err).await,
+                },
+                accepted = accept_unix(&self.unix_listener) => match accepted {
+                    Ok((socket, role)) => self.spawn_client(socket, role, None),
+                    Err(err) => accept_failed("unix socket", err).await,
+                },
+            }
+        }
+    }
 
-            let tasks_manager = self.tasks_manager.clone();
+    /// The tcp clients are trusted, once authenticated if a secret is configured.
+    fn spawn_tcp_client(&self, socket: TcpStream) {
+        let Some(acceptor) = self.tls_acceptor.clone() else {
+            return self.spawn_client(socket, Some(Role::Admin), self.secret.clone());
+        };
+        let tasks_manager = self.tasks_manager.clone();
+        let secret = self.secret.clone();
 
-            tokio::spawn(async move {
-                let client_id = ClientId::from(socket.as_raw_fd());
-                ClientHandler::process_client(socket, tasks_manager, client_id)
-                    .await
-                    .inspect_err(|err| eprintln!("ClientHandler error: {err:?}"))
-            });
-        }
+        tokio::spawn(async move {
+            let client_id = ClientId::from(socket.as_raw_fd());
+            match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(socket)).await {
+                Ok(Ok(stream)) => {
+                    serve_client(stream, tasks_manager, client_id, Some(Role::Admin), secret).await
+                }
+                Ok(Err(err)) => eprintln!("Client {client_id} failed the TLS handshake: {err}"),
+                Err(_) => {
+                    eprintln!("Client {client_id} did not complete the TLS handshake in time")
+                }
+            }
+        });
+    }
+
+    fn spawn_client<Stream>(&self, socket: Stream, role: Option<Role>, secret: Option<Arc<[u8]>>)
+    where
+        Stream: AsyncRead + AsyncWrite + AsRawFd + Unpin + Send + 'static,
+    {
+        let tasks_manager = self.tasks_manager.clone();
+
+        tokio::spawn(async move {
+            let client_id = ClientId::from(socket.as_raw_fd());
+            serve_client(socket, tasks_manager, client_id, role, secret).await
+        });
+    }
+}
+
+async fn serve_client<Stream>(
+    socket: Stream,
+    tasks_manager: tasks_manager::Handle,
+    client_id: ClientId,
+    role: Option<Role>,
+    secret: Option<Arc<[u8]>>,
+) where
+    Stream: AsyncRead + AsyncWrite + Unpin,
+{
+    let _ =
+        ClientHandler::process_client(socket, tasks_manager, client_id, role, secret.as_deref())
+            .await
+            .inspect_err(|err| eprintln!("ClientHandler error: {err:?}"));
+}
+
+/// Joins `host` and `port`, with the brackets an IPv6 address needs.
+fn tcp_address(host: &str, port: i32) -> String {
+    if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
+}
+
+/// Failing to accept a client does not stop the server, the error may only last until a file
+/// descriptor is released. Waits a bit to not spin on it.
+async fn accept_failed(listener: &str, error: io::Error) {
+    eprintln!("Taskmaster error: Failed to accept a {listener} client: {error}");
+    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+}
+
+/// Never resolves when the listener is disabled.
+async fn accept_tcp(listener: &Option<TcpListener>) -> io::Result<TcpStream> {
+    match listener {
+        Some(listener) => listener.accept().await.map(|(socket, _)| socket),
+        None => std::future::pending().await,
+    }
+}
+
+/// Never resolves when the listener is disabled.
+async fn accept_unix(
+    listener: &Option<UnixSocketListener>,
+) -> io::Result<(UnixStream, Option<Role>)> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
     }
 }
@@ -0,0 +1,205 @@
+use super::error::{Error, Result};
+use crate::config::Tls;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+use std::{path::Path, sync::Arc};
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{RootCertStore, ServerConfig, server::WebPkiClientVerifier},
+};
+
+/// Builds the acceptor wrapping the tcp connections, which checks the certificates of the
+/// clients when `client_ca_file` is set.
+pub(super) fn acceptor(config: &Tls) -> Result<TlsAcceptor> {
+    let certs = load_certs(&config.cert_file)?;
+    let key = PrivateKeyDer::from_pem_file(&config.key_file).map_err(|error| Error::LoadPem {
+        path: config.key_file.clone(),
+        error,
+    })?;
+
+    let builder = ServerConfig::builder();
+    let builder = match &config.client_ca_file {
+        Some(client_ca_file) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(client_ca_file)? {
+                roots.add(cert).map_err(Error::Tls)?;
+            }
+            let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+                .build()
+                .map_err(Error::ClientVerifier)?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let server_config = builder.with_single_cert(certs, key).map_err(Error::Tls)?;
+
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let pem_error = |error| Error::LoadPem {
+        path: path.to_path_buf(),
+        error,
+    };
+    CertificateDer::pem_file_iter(path)
+        .map_err(pem_error)?
+        .collect::<std::result::Result<_, _>>()
+        .map_err(pem_error)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rcgen::{BasicConstraints, CertificateParams, CertifiedKey, IsCa, KeyPair};
+    use std::{fs, path::PathBuf};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+    use tokio_rustls::{
+        TlsConnector,
+        rustls::{ClientConfig, pki_types::ServerName},
+    };
+
+    struct Authority {
+        cert: rcgen::Certificate,
+        key: KeyPair,
+    }
+
+    impl Authority {
+        fn new() -> Self {
+            let mut params = CertificateParams::new(Vec::new()).unwrap();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let key = KeyPair::generate().unwrap();
+            let cert = params.self_signed(&key).unwrap();
+            Self { cert, key }
+        }
+
+        fn sign(&self, name: &str) -> CertifiedKey {
+            let params = CertificateParams::new(vec![name.to_string()]).unwrap();
+            let key_pair = KeyPair::generate().unwrap();
+            let cert = params.signed_by(&key_pair, &self.cert, &self.key).unwrap();
+            CertifiedKey { cert, key_pair }
+        }
+    }
+
+    fn write_pem(dir: &Path, name: &str, content: String) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("taskmaster-test-tls-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn server_config(dir: &Path, authority: &Authority, verify_clients: bool) -> Tls {
+        let server = authority.sign("localhost");
+        Tls {
+            cert_file: write_pem(dir, "cert.pem", server.cert.pem()),
+            key_file: write_pem(dir, "key.pem", server.key_pair.serialize_pem()),
+            client_ca_file: verify_clients
+                .then(|| write_pem(dir, "clients.pem", authority.cert.pem())),
+        }
+    }
+
+    fn connector(authority: &Authority, client: Option<CertifiedKey>) -> TlsConnector {
+        let mut roots = RootCertStore::empty();
+        roots.add(authority.cert.der().clone()).unwrap();
+        let builder = ClientConfig::builder().with_root_certificates(roots);
+        let config = match client {
+            Some(client) => builder
+                .with_client_auth_cert(
+                    vec![client.cert.der().clone()],
+                    PrivateKeyDer::try_from(client.key_pair.serialize_der()).unwrap(),
+                )
+                .unwrap(),
+            None => builder.with_no_client_auth(),
+        };
+        TlsConnector::from(Arc::new(config))
+    }
+
+    /// Sends a byte over a TLS connection and returns the one echoed by the server, or the error
+    /// of the first side that failed.
+    async fn echo(acceptor: TlsAcceptor, connector: TlsConnector) -> std::io::Result<u8> {
+        let listener = TcpListener::bind("localhost:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await?;
+            let mut stream = acceptor.accept(socket).await?;
+            let byte = stream.read_u8().await?;
+            stream.write_u8(byte).await?;
+            stream.flush().await
+        });
+
+        let client = async {
+            let socket = TcpStream::connect(addr).await?;
+            let name = ServerName::try_from("localhost").unwrap();
+            let mut stream = connector.connect(name, socket).await?;
+            stream.write_u8(42).await?;
+            stream.flush().await?;
+            stream.read_u8().await
+        };
+
+        let echoed = client.await;
+        server.await.unwrap()?;
+        echoed
+    }
+
+    #[tokio::test]
+    async fn test_tls() {
+        let dir = temp_dir("server");
+        let authority = Authority::new();
+        let acceptor = acceptor(&server_config(&dir, &authority, false)).unwrap();
+
+        assert_eq!(
+            echo(acceptor, connector(&authority, None)).await.unwrap(),
+            42
+        );
+
+        // The client does not trust a server signed by another authority
+        let acceptor = super::acceptor(&server_config(&dir, &Authority::new(), false)).unwrap();
+        assert!(echo(acceptor, connector(&authority, None)).await.is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tls_client_verification() {
+        let dir = temp_dir("client");
+        let authority = Authority::new();
+        let config = server_config(&dir, &authority, true);
+
+        let client = authority.sign("client");
+        let echoed = echo(
+            acceptor(&config).unwrap(),
+            connector(&authority, Some(client)),
+        )
+        .await;
+        assert_eq!(echoed.unwrap(), 42);
+
+        let echoed = echo(acceptor(&config).unwrap(), connector(&authority, None)).await;
+        assert!(echoed.is_err());
+
+        let client = Authority::new().sign("client");
+        let echoed = echo(
+            acceptor(&config).unwrap(),
+            connector(&authority, Some(client)),
+        )
+        .await;
+        assert!(echoed.is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_files() {
+        let config = Tls {
+            cert_file: "/nonexistent/cert.pem".into(),
+            key_file: "/nonexistent/key.pem".into(),
+            client_ca_file: None,
+        };
+        assert!(matches!(acceptor(&config), Err(Error::LoadPem { .. })));
+    }
+}
@@ -0,0 +1,315 @@
+use super::error::{Error, Result};
+use crate::config::{RoleMembers, Roles, UnixSocket};
+use commands::Role;
+use libc::pwd::{getgrnam, getgrouplist, getpwnam, getpwuid, gid_t, uid_t};
+use std::{
+    ffi::{CString, c_char, c_int},
+    fs::{self, DirBuilder, Permissions},
+    io,
+    os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt, chown},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tokio::net::{UnixListener, UnixStream};
+
+pub(super) struct UnixSocketListener {
+    listener: UnixListener,
+    /// Every client is an admin when unset
+    roles: Option<RoleMap>,
+}
+
+impl UnixSocketListener {
+    /// Binds the socket, replacing the one a previous daemon left behind, with its mode and
+    /// owner.
+    ///
+    /// The socket is bound in a directory only taskmaster can access, and moved in place once its
+    /// mode and owner are applied, so that the clients they exclude can never connect to it.
+    pub(super) fn bind(config: &UnixSocket) -> Result<Self> {
+        let roles = config.roles.as_ref().map(RoleMap::resolve).transpose()?;
+
+        let path = &config.path;
+        let io_error = |error| Error::BindUnixListener {
+            path: path.clone(),
+            error,
+        };
+
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                fs::remove_file(path).map_err(io_error)?
+            }
+            // Not replaced by the socket moved in place
+            Ok(_) => return Err(io_error(io::ErrorKind::AddrInUse.into())),
+            Err(_) => {}
+        }
+
+        let mut staging_dir = path.clone().into_os_string();
+        staging_dir.push(format!(".{}", std::process::id()));
+        let staging_dir = PathBuf::from(staging_dir);
+        DirBuilder::new()
+            .mode(0o700)
+            .create(&staging_dir)
+            .map_err(io_error)?;
+        let listener = Self::bind_staged(config, &staging_dir.join("socket"));
+        let _ = fs::remove_dir_all(&staging_dir);
+
+        Ok(Self {
+            listener: listener?,
+            roles,
+        })
+    }
+
+    /// Binds the socket at `staged`, applies its mode and owner, then moves it to its path.
+    fn bind_staged(config: &UnixSocket, staged: &Path) -> Result<UnixListener> {
+        let io_error = |error| Error::BindUnixListener {
+            path: config.path.clone(),
+            error,
+        };
+
+        let listener = UnixListener::bind(staged).map_err(io_error)?;
+        fs::set_permissions(staged, Permissions::from_mode(config.mode)).map_err(io_error)?;
+        if let Some(owner) = &config.owner {
+            let (uid, gid) = resolve_owner(owner)?;
+            chown(staged, Some(uid), gid).map_err(io_error)?;
+        }
+        fs::rename(staged, &config.path).map_err(io_error)?;
+        Ok(listener)
+    }
+
+    /// Accepts a client along with the role it gets from the credentials of its process.
+    pub(super) async fn accept(&self) -> io::Result<(UnixStream, Option<Role>)> {
+        let (socket, _) = self.listener.accept().await?;
+        let role = match &self.roles {
+            Some(roles) => {
+                let credentials = socket.peer_cred()?;
+                let (uid, gid) = (credentials.uid(), credentials.gid());
+                // The lookup may have to query a directory service, and block for a while
+                let groups = tokio::task::spawn_blocking(move || groups_of(uid, gid))
+                    .await
+                    .map_err(io::Error::other)?;
+                roles.role_of(uid, &groups)
+            }
+            None => Some(Role::Admin),
+        };
+        Ok((socket, role))
+    }
+}
+
+/// `Roles` with the names resolved to ids.
+struct RoleMap {
+    /// Highest role first
+    members: Vec<(Role, Vec<uid_t>, Vec<gid_t>)>,
+}
+
+impl RoleMap {
+    fn resolve(roles: &Roles) -> Result<Self> {
+        let resolve_members = |role, members: &RoleMembers| -> Result<_> {
+            let uids = members
+                .users
+                .iter()
+                .map(|user| resolve_user(user))
+                .collect::<Result<_>>()?;
+            let gids = members
+                .groups
+                .iter()
+                .map(|group| resolve_group(group))
+                .collect::<Result<_>>()?;
+            Ok((role, uids, gids))
+        };
+
+        Ok(Self {
+            members: vec![
+                resolve_members(Role::Admin, &roles.admin)?,
+                resolve_members(Role::Operator, &roles.operator)?,
+                resolve_members(Role::ReadOnly, &roles.read_only)?,
+            ],
+        })
+    }
+
+    fn role_of(&self, uid: uid_t, groups: &[gid_t]) -> Option<Role> {
+        self.members
+            .iter()
+            .find(|(_, uids, gids)| {
+                uids.contains(&uid) || groups.iter().any(|group| gids.contains(group))
+            })
+            .map(|(role, _, _)| *role)
+    }
+}
+
+/// Resolves `user` or `user:group` to ids.
+fn resolve_owner(owner: &str) -> Result<(uid_t, Option<gid_t>)> {
+    match owner.split_once(':') {
+        Some((user, group)) => Ok((resolve_user(user)?, Some(resolve_group(group)?))),
+        None => Ok((resolve_user(owner)?, None)),
+    }
+}
+
+/// Resolves a user name or a numeric uid.
+fn resolve_user(user: &str) -> Result<uid_t> {
+    match user.parse() {
+        Ok(uid) => Ok(uid),
+        Err(_) => lookup(user, |name| unsafe {
+            getpwnam(name).as_ref().map(|pw| pw.pw_uid)
+        })
+        .ok_or_else(|| Error::UnknownUser(user.to_string())),
+    }
+}
+
+/// Resolves a group name or a numeric gid.
+fn resolve_group(group: &str) -> Result<gid_t> {
+    match group.parse() {
+        Ok(gid) => Ok(gid),
+        Err(_) => lookup(group, |name| unsafe {
+            getgrnam(name).as_ref().map(|gr| gr.gr_gid)
+        })
+        .ok_or_else(|| Error::UnknownGroup(group.to_string())),
+    }
+}
+
+/// `getpwuid` returns a pointer to static storage.
+static PASSWD_LOCK: Mutex<()> = Mutex::new(());
+
+/// The primary group `gid` of the user `uid` along with its supplementary groups. Only the
+/// primary group is known when the user has no entry.
+fn groups_of(uid: uid_t, gid: gid_t) -> Vec<gid_t> {
+    let _guard = PASSWD_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Some(name) = (unsafe { getpwuid(uid).as_ref() }).map(|pw| pw.pw_name) else {
+        return vec![gid];
+    };
+
+    let mut groups: Vec<gid_t> = vec![0; 16];
+    loop {
+        let mut count = groups.len() as c_int;
+        let found = unsafe { getgrouplist(name, gid, groups.as_mut_ptr(), &mut count) };
+        if found != -1 {
+            groups.truncate(count as usize);
+            return groups;
+        }
+        // `count` holds the number of groups on Linux only
+        let len = (count as usize).max(groups.len() * 2);
+        groups.resize(len, 0);
+    }
+}
+
+fn lookup<Id>(name: &str, getter: impl FnOnce(*const c_char) -> Option<Id>) -> Option<Id> {
+    let name = CString::new(name).ok()?;
+    getter(name.as_ptr())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+
+    fn socket_config(name: &str, roles: Option<Roles>) -> UnixSocket {
+        let path = std::env::temp_dir().join(format!(
+            "taskmaster-test-{name}-{}.sock",
+            std::process::id()
+        ));
+        UnixSocket {
+            path,
+            mode: 0o640,
+            owner: None,
+            roles,
+        }
+    }
+
+    fn members(users: &[&str], groups: &[&str]) -> RoleMembers {
+        RoleMembers {
+            users: users.iter().map(|user| user.to_string()).collect(),
+            groups: groups.iter().map(|group| group.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_owner() {
+        assert_eq!(resolve_owner("0").unwrap(), (0, None));
+        assert_eq!(resolve_owner("1000:100").unwrap(), (1000, Some(100)));
+        assert_eq!(resolve_owner("root").unwrap(), (0, None));
+        assert_eq!(resolve_owner("root:0").unwrap(), (0, Some(0)));
+        assert!(matches!(
+            resolve_owner("taskmaster-no-such-user"),
+            Err(Error::UnknownUser(_))
+        ));
+        assert!(matches!(
+            resolve_owner("root:taskmaster-no-such-group"),
+            Err(Error::UnknownGroup(_))
+        ));
+    }
+
+    #[test]
+    fn test_role_of() {
+        let roles = RoleMap::resolve(&Roles {
+            read_only: members(&[], &["100"]),
+            operator: members(&["1000"], &[]),
+            admin: members(&["root"], &[]),
+        })
+        .unwrap();
+
+        assert_eq!(roles.role_of(0, &[0]), Some(Role::Admin));
+        assert_eq!(roles.role_of(1000, &[100]), Some(Role::Operator));
+        assert_eq!(roles.role_of(1001, &[100]), Some(Role::ReadOnly));
+        assert_eq!(roles.role_of(1001, &[1001, 100]), Some(Role::ReadOnly));
+        assert_eq!(roles.role_of(1001, &[1001]), None);
+    }
+
+    #[test]
+    fn test_groups_of() {
+        assert!(groups_of(0, 0).contains(&0));
+        // Only the primary group is known without a user entry
+        assert_eq!(groups_of(4_000_000_000, 42), vec![42]);
+    }
+
+    #[tokio::test]
+    async fn test_bind() {
+        let config = socket_config("bind", None);
+        let path = config.path.clone();
+
+        let listener = UnixSocketListener::bind(&config).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+        drop(listener);
+
+        // The socket left behind is replaced
+        let listener = UnixSocketListener::bind(&config).unwrap();
+        let _client = UnixStream::connect(&path).await.unwrap();
+        let (_, role) = listener.accept().await.unwrap();
+        assert_eq!(role, Some(Role::Admin));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bind_over_file() {
+        let config = socket_config("bind-over-file", None);
+        fs::write(&config.path, "not a socket").unwrap();
+
+        assert!(matches!(
+            UnixSocketListener::bind(&config),
+            Err(Error::BindUnixListener { error, .. }) if error.kind() == io::ErrorKind::AddrInUse
+        ));
+        assert_eq!(fs::read_to_string(&config.path).unwrap(), "not a socket");
+        fs::remove_file(&config.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_accept_with_roles() {
+        let mut config = socket_config("roles", Some(Roles::default()));
+        let listener = UnixSocketListener::bind(&config).unwrap();
+        let _client = UnixStream::connect(&config.path).await.unwrap();
+        let (_, role) = listener.accept().await.unwrap();
+        assert_eq!(role, None);
+
+        // The socket is owned by the user running the test
+        let uid = fs::metadata(&config.path).unwrap().uid();
+        config.roles = Some(Roles {
+            operator: members(&[&uid.to_string()], &[]),
+            ..Default::default()
+        });
+        let listener = UnixSocketListener::bind(&config).unwrap();
+        let _client = UnixStream::connect(&config.path).await.unwrap();
+        let (_, role) = listener.accept().await.unwrap();
+        assert_eq!(role, Some(Role::Operator));
+        fs::remove_file(&config.path).unwrap();
+    }
+}
@@ -1,7 +1,46 @@
-use super::Result;
+use super::{EventReceiver, LogLineReceiver, Result, StartOutcome};
+use commands::{EventFilter, LogLine, LogStream, SignalResult};
 use mockall::automock;
+use signal::Signal;
 
 #[automock]
 pub trait Api {
     async fn list_tasks(&self) -> Result<Vec<String>>;
+    /// Starts the processes of `target` that are not running, and waits for them to reach
+    /// `Running` with `wait`. Returns `None` if `target` does not match any program or instance.
+    async fn start(&self, target: String, wait: bool) -> Result<Option<StartOutcome>>;
+    /// Waits for every process of `target` to be `Running`, for at most `timeout` seconds or the
+    /// time starting them would take. Returns `false` if one is not running or did not get there
+    /// in time, and `None` if `target` does not match any program or instance.
+    async fn wait(&self, target: String, timeout: Option<u32>) -> Result<Option<bool>>;
+    /// Stops the processes of `target` and waits for them to exit. `timeout` overrides the
+    /// `stoptime` of the program and `force` sends SIGKILL right away. Returns `false` if
+    /// `target` does not match any program or instance.
+    async fn stop(&self, target: String, timeout: Option<u32>, force: bool) -> Result<bool>;
+    async fn reopen_logs(&self) -> Result<()>;
+    /// Returns `None` if `target` does not match any program or instance.
+    async fn tail(
+        &self,
+        target: String,
+        lines: usize,
+        stream: Option<LogStream>,
+    ) -> Result<Option<Vec<LogLine>>>;
+    /// Returns the first of `targets` that does not match any program or instance as an error.
+    async fn follow_logs(
+        &self,
+        targets: Vec<String>,
+        streams: Vec<LogStream>,
+    ) -> Result<std::result::Result<LogLineReceiver, String>>;
+    /// Returns the events selected by `filter` that happen from now on.
+    async fn subscribe(&self, filter: EventFilter) -> Result<EventReceiver>;
+    /// Tells the subscribers that a client connected, without waiting for them.
+    async fn client_connected(&self, client_id: i32) -> Result<()>;
+    /// Sends `signal` to every running process of `target`, or to their process group. Returns
+    /// `None` if `target` does not match any program or instance.
+    async fn signal(
+        &self,
+        target: String,
+        signal: Signal,
+        process_group: bool,
+    ) -> Result<Option<Vec<SignalResult>>>;
 }
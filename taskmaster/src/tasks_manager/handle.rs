@@ -2,6 +2,9 @@ use super::Api;
 use super::Message;
 use super::error::{CallError, CastError, Result};
 use super::routine;
+use super::{EventReceiver, LogLineReceiver, StartOutcome};
+use commands::{EventFilter, LogLine, LogStream, SignalResult};
+use signal::Signal;
 use tokio::sync::oneshot;
 
 #[derive(Clone)]
@@ -13,6 +16,90 @@ impl Api for Handle {
     async fn list_tasks(&self) -> Result<Vec<String>> {
         self.call(Message::ListTasks).await
     }
+
+    async fn start(&self, target: String, wait: bool) -> Result<Option<StartOutcome>> {
+        self.call(|sender| Message::Start {
+            target,
+            wait,
+            sender,
+        })
+        .await
+    }
+
+    async fn wait(&self, target: String, timeout: Option<u32>) -> Result<Option<bool>> {
+        self.call(|sender| Message::Wait {
+            target,
+            timeout,
+            sender,
+        })
+        .await
+    }
+
+    async fn stop(&self, target: String, timeout: Option<u32>, force: bool) -> Result<bool> {
+        self.call(|sender| Message::Stop {
+            target,
+            timeout,
+            force,
+            sender,
+        })
+        .await
+    }
+
+    async fn reopen_logs(&self) -> Result<()> {
+        self.call(Message::ReopenLogs).await
+    }
+
+    async fn tail(
+        &self,
+        target: String,
+        lines: usize,
+        stream: Option<LogStream>,
+    ) -> Result<Option<Vec<LogLine>>> {
+        self.call(|sender| Message::Tail {
+            target,
+            lines,
+            stream,
+            sender,
+        })
+        .await
+    }
+
+    async fn follow_logs(
+        &self,
+        targets: Vec<String>,
+        streams: Vec<LogStream>,
+    ) -> Result<std::result::Result<LogLineReceiver, String>> {
+        self.call(|sender| Message::FollowLogs {
+            targets,
+            streams,
+            sender,
+        })
+        .await
+    }
+
+    async fn subscribe(&self, filter: EventFilter) -> Result<EventReceiver> {
+        self.call(|sender| Message::Subscribe { filter, sender })
+            .await
+    }
+
+    async fn client_connected(&self, client_id: i32) -> Result<()> {
+        self.cast(Message::ClientConnected { client_id }).await
+    }
+
+    async fn signal(
+        &self,
+        target: String,
+        signal: Signal,
+        process_group: bool,
+    ) -> Result<Option<Vec<SignalResult>>> {
+        self.call(|sender| Message::Signal {
+            target,
+            signal,
+            process_group,
+            sender,
+        })
+        .await
+    }
 }
 
 impl Handle {
@@ -38,7 +125,7 @@ impl Handle {
     }
 
     /// Sends a message to the tasks manager process without waiting for a response.
-    async fn _cast(&self, message: Message) -> Result<()> {
+    async fn cast(&self, message: Message) -> Result<()> {
         Ok(self
             .sender
             .send(message)
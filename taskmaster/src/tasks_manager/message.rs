@@ -1,6 +1,59 @@
+use super::{EventReceiver, LogLineReceiver, StartOutcome};
+use commands::{EventFilter, LogLine, LogStream, SignalResult};
+use signal::Signal;
 use tokio::sync::oneshot;
 
 #[derive(Debug)]
 pub enum Message {
     ListTasks(oneshot::Sender<Vec<String>>),
+    /// Responds with `None` if the target does not match any program or instance, and once the
+    /// processes are `Running` with `wait`
+    Start {
+        target: String,
+        wait: bool,
+        sender: oneshot::Sender<Option<StartOutcome>>,
+    },
+    /// Responds with `None` if the target does not match any program or instance, and otherwise
+    /// once its processes are `Running` or gave up
+    Wait {
+        target: String,
+        timeout: Option<u32>,
+        sender: oneshot::Sender<Option<bool>>,
+    },
+    /// Responds once every process of the target has exited, with `false` if the target does
+    /// not match any program or instance
+    Stop {
+        target: String,
+        timeout: Option<u32>,
+        force: bool,
+        sender: oneshot::Sender<bool>,
+    },
+    ReopenLogs(oneshot::Sender<()>),
+    /// Responds with `None` if the target does not match any program or instance
+    Tail {
+        target: String,
+        lines: usize,
+        stream: Option<LogStream>,
+        sender: oneshot::Sender<Option<Vec<LogLine>>>,
+    },
+    /// Responds with the first target that does not match any program or instance, if any
+    FollowLogs {
+        targets: Vec<String>,
+        streams: Vec<LogStream>,
+        sender: oneshot::Sender<Result<LogLineReceiver, String>>,
+    },
+    Subscribe {
+        filter: EventFilter,
+        sender: oneshot::Sender<EventReceiver>,
+    },
+    ClientConnected {
+        client_id: i32,
+    },
+    /// Responds with `None` if the target does not match any program or instance
+    Signal {
+        target: String,
+        signal: Signal,
+        process_group: bool,
+        sender: oneshot::Sender<Option<Vec<SignalResult>>>,
+    },
 }
@@ -17,6 +17,26 @@ pub use api::Api;
 pub use api::MockApi;
 
 use crate::config::Program;
+use commands::{Event, LogLine};
+use tokio::sync::mpsc;
+
+/// Receives the logs followed by a client, closed once every followed process is gone.
+pub type LogLineReceiver = mpsc::Receiver<LogLine>;
+
+/// Receives the events a client subscribed to, closed when the tasks manager is gone.
+pub type EventReceiver = mpsc::Receiver<Event>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartOutcome {
+    /// Every missing process was spawned, and reached `Running` when waited for
+    Started,
+    /// A process failed to spawn, or did not reach `Running` when waited for
+    Failed,
+    /// There was no process to start
+    AlreadyRunning,
+    /// A process is still stopping, it cannot be started again before it exited
+    Stopping,
+}
 
 pub async fn spawn(tasks: Vec<Program>) -> Handle {
     Routine::spawn(tasks).await
@@ -1,35 +1,744 @@
-use super::Handle;
 use super::Message;
+use super::{Api, Handle};
+use super::{EventReceiver, LogLineReceiver, StartOutcome};
 use crate::config::Program;
-use tokio::sync::mpsc;
+use crate::process_handler::{self, Hub, KillCommand, LogType, RoutineSpawnError, Status};
+use commands::{Event, EventFilter, LogLine, LogStream, SignalResult};
+use signal::Signal;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinSet;
 
 pub type Sender = mpsc::Sender<Message>;
 
+const LOG_LINE_CHANNEL_CAPACITY: usize = 256;
+/// Number of events kept for the subscribers that are late to receive them, older events are
+/// dropped.
+const EVENT_HUB_CAPACITY: usize = 256;
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+/// Time given to the processes waited for by `start` to be `Running`, on top of the time given to
+/// their start attempts
+const START_WAIT_MARGIN: Duration = Duration::from_secs(10);
+
 pub struct Routine {
     tasks: Vec<Program>,
+    /// Handles of the running processes, by program name
+    processes: HashMap<String, Vec<process_handler::Handle>>,
     receiver: mpsc::Receiver<Message>,
+    events: Hub<Event>,
 }
 
 impl Routine {
     pub(super) async fn spawn(tasks: Vec<Program>) -> Handle {
         let (sender, receiver) = mpsc::channel(100);
 
+        let events = Hub::new(EVENT_HUB_CAPACITY);
+        let processes = Self::autostart(&tasks, &events).await;
+        let dependents = Self::dependents(&tasks);
+
         tokio::spawn(async move {
-            Self { tasks, receiver }.event_loop().await;
+            Self {
+                tasks,
+                processes,
+                receiver,
+                events,
+            }
+            .event_loop()
+            .await;
         });
 
-        Handle::new(sender)
+        let handle = Handle::new(sender);
+        if !dependents.is_empty() {
+            tokio::spawn(Self::start_dependents(handle.clone(), dependents));
+        }
+        handle
     }
 
+    /// Spawns `numprocs` processes for every program with `autostart` enabled and no
+    /// dependencies, see `start_dependents` for the others.
+    async fn autostart(
+        tasks: &[Program],
+        events: &Hub<Event>,
+    ) -> HashMap<String, Vec<process_handler::Handle>> {
+        let mut processes = HashMap::new();
+
+        for task in tasks
+            .iter()
+            .filter(|task| *task.auto_start() && task.depends_on().is_empty())
+        {
+            let mut handles = Vec::new();
+            for instance in 0..*task.num_procs() {
+                match Self::spawn_process(task, instance, &handles).await {
+                    Ok(mut handle) => {
+                        Self::publish_process_events(events, task.name(), &mut handle, None);
+                        handles.push(handle);
+                    }
+                    Err(err) => eprintln!("Taskmaster error: {err}"),
+                }
+            }
+            processes.insert(task.name().clone(), handles);
+        }
+
+        processes
+    }
+
+    /// Programs with `autostart` enabled and dependencies, ordered so that every program comes
+    /// after the ones it depends on. Programs depending on each other are left out.
+    fn dependents(tasks: &[Program]) -> Vec<Program> {
+        let mut pending: Vec<_> = tasks
+            .iter()
+            .filter(|task| *task.auto_start() && !task.depends_on().is_empty())
+            .collect();
+        let mut ordered = Vec::new();
+
+        while !pending.is_empty() {
+            let (ready, blocked): (Vec<&Program>, _) = pending.iter().partition(|task| {
+                task.depends_on()
+                    .iter()
+                    .all(|dependency| pending.iter().all(|other| other.name() != dependency))
+            });
+            if ready.is_empty() {
+                for task in blocked {
+                    eprintln!(
+                        "Taskmaster error: {}: Not started, its dependencies form a cycle",
+                        task.name()
+                    );
+                }
+                break;
+            }
+            ordered.extend(ready.into_iter().cloned());
+            pending = blocked;
+        }
+
+        ordered
+    }
+
+    /// Starts the `dependents` one after the other, each once its dependencies are `Running`.
+    /// A program is not started if one of its dependencies is not running or does not get there
+    /// in time.
+    async fn start_dependents(manager: Handle, dependents: Vec<Program>) {
+        'dependents: for program in dependents {
+            let name = program.name();
+            for dependency in program.depends_on() {
+                let error = match manager.wait(dependency.clone(), None).await {
+                    Ok(Some(true)) => continue,
+                    Ok(Some(false)) => format!("`{dependency}` is not running"),
+                    Ok(None) => format!("no such program `{dependency}`"),
+                    Err(err) => format!("{err}"),
+                };
+                eprintln!("Taskmaster error: {name}: Not started, {error}");
+                continue 'dependents;
+            }
+
+            match manager.start(name.clone(), false).await {
+                Ok(Some(StartOutcome::Started | StartOutcome::AlreadyRunning)) => {}
+                Ok(_) => eprintln!("Taskmaster error: {name}: Failed to start"),
+                Err(err) => eprintln!("Taskmaster error: {name}: Failed to start: {err}"),
+            }
+        }
+    }
+
+    /// Replies are sent with their errors ignored, the client may have given up on the request
+    /// and dropped its receiver.
     async fn event_loop(mut self) {
         while let Some(message) = self.receiver.recv().await {
             match message {
                 Message::ListTasks(sender) => {
-                    sender
-                        .send(self.tasks.iter().map(|t| format!("{t}")).collect())
-                        .unwrap();
+                    let _ = sender.send(self.tasks.iter().map(|t| format!("{t}")).collect());
+                }
+                Message::Start {
+                    target,
+                    wait,
+                    sender,
+                } => self.start(&target, wait, sender).await,
+                Message::Wait {
+                    target,
+                    timeout,
+                    sender,
+                } => self.wait(&target, timeout, sender),
+                Message::Stop {
+                    target,
+                    timeout,
+                    force,
+                    sender,
+                } => self.stop(&target, timeout, force, sender),
+                Message::ReopenLogs(sender) => {
+                    self.reopen_logs().await;
+                    let _ = sender.send(());
+                }
+                Message::Tail {
+                    target,
+                    lines,
+                    stream,
+                    sender,
+                } => {
+                    let _ = sender.send(self.tail(&target, lines, stream));
+                }
+                Message::FollowLogs {
+                    targets,
+                    streams,
+                    sender,
+                } => {
+                    let _ = sender.send(self.follow_logs(&targets, streams));
+                }
+                Message::Subscribe { filter, sender } => {
+                    let _ = sender.send(self.subscribe(filter));
+                }
+                Message::ClientConnected { client_id } => {
+                    self.events.send(Event::ClientConnected { client_id });
+                }
+                Message::Signal {
+                    target,
+                    signal,
+                    process_group,
+                    sender,
+                } => {
+                    let _ = sender.send(self.signal(&target, signal, process_group));
+                }
+            }
+        }
+    }
+
+    /// Spawns the `instance`-th process of `program`. It writes to the output files of the other
+    /// instances in `handles` if there are any, so that they do not each truncate and rotate the
+    /// same files.
+    async fn spawn_process(
+        program: &Program,
+        instance: u32,
+        handles: &[process_handler::Handle],
+    ) -> Result<process_handler::Handle, RoutineSpawnError> {
+        match handles.first() {
+            Some(handle) => Ok(process_handler::Routine::spawn_with_output_files(
+                program.clone(),
+                instance,
+                handle.output_files.clone(),
+            )),
+            None => process_handler::Routine::spawn(program.clone(), instance).await,
+        }
+    }
+
+    async fn reopen_logs(&self) {
+        for (name, handles) in self.processes.iter() {
+            for handle in handles {
+                let _ = handle.output_files.reopen().await.inspect_err(|err| {
+                    eprintln!("Taskmaster error: {name}: Failed to reopen log files: {err}")
+                });
+            }
+        }
+    }
+
+    /// Parses `target`, either `program` for every instance of a program or `program:instance`
+    /// for a single one. Returns `None` if there is no such program or instance.
+    fn parse_target(&self, target: &str) -> Option<(&Program, Option<u32>)> {
+        let (name, instance) = match target.rsplit_once(':') {
+            Some((name, instance)) => (name, Some(instance.parse::<u32>().ok()?)),
+            None => (target, None),
+        };
+        let program = self.tasks.iter().find(|task| task.name() == name)?;
+
+        match instance {
+            Some(instance) if instance >= *program.num_procs() => None,
+            instance => Some((program, instance)),
+        }
+    }
+
+    /// Finds the processes designated by `target`, see `parse_target`. Returns `None` if there is
+    /// no such program or instance, and an empty list for a program that is not running.
+    fn find_processes(&self, target: &str) -> Option<Vec<&process_handler::Handle>> {
+        let (program, instance) = self.parse_target(target)?;
+        let handles = self
+            .processes
+            .get(program.name())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        Some(
+            handles
+                .iter()
+                .filter(|handle| instance.is_none_or(|instance| handle.instance == instance))
+                .collect(),
+        )
+    }
+
+    /// Spawns the processes of `target` whose routine is not running. With `wait`, the response
+    /// is sent from another task once every process is `Running` or gave up starting, or after a
+    /// deadline, so that the tasks manager keeps handling messages in the meantime.
+    async fn start(
+        &mut self,
+        target: &str,
+        wait: bool,
+        sender: oneshot::Sender<Option<StartOutcome>>,
+    ) {
+        let Some((program, instance)) = self.parse_target(target) else {
+            let _ = sender.send(None);
+            return;
+        };
+        let program = program.clone();
+        let instances = match instance {
+            Some(instance) => instance..instance + 1,
+            None => 0..*program.num_procs(),
+        };
+
+        let handles = self.processes.entry(program.name().clone()).or_default();
+        handles.retain(|handle| !handle.is_over());
+
+        let mut started = true;
+        let mut stopping = false;
+        let mut readiness = Vec::new();
+        for instance in instances {
+            if let Some(handle) = handles.iter().find(|handle| handle.instance == instance) {
+                stopping |= handle.stopping;
+                continue;
+            }
+            match Self::spawn_process(&program, instance, handles).await {
+                Ok(mut handle) => {
+                    let (ready_sender, ready_receiver) = oneshot::channel();
+                    Self::publish_process_events(
+                        &self.events,
+                        program.name(),
+                        &mut handle,
+                        Some(ready_sender),
+                    );
+                    readiness.push(ready_receiver);
+                    handles.push(handle);
+                }
+                Err(err) => {
+                    eprintln!("Taskmaster error: {err}");
+                    started = false;
+                }
+            }
+        }
+
+        let outcome = |started| {
+            if started {
+                StartOutcome::Started
+            } else {
+                StartOutcome::Failed
+            }
+        };
+        if started && readiness.is_empty() {
+            let outcome = if stopping {
+                StartOutcome::Stopping
+            } else {
+                StartOutcome::AlreadyRunning
+            };
+            let _ = sender.send(Some(outcome));
+            return;
+        }
+        if !wait {
+            let _ = sender.send(Some(outcome(started)));
+            return;
+        }
+        let deadline = Self::start_deadline(&program);
+        tokio::spawn(async move {
+            let wait = async {
+                for ready in readiness {
+                    // Dropped without being sent when the routine gave up before `Running`
+                    started &= ready.await.is_ok();
+                }
+                started
+            };
+            let started = tokio::time::timeout(deadline, wait).await.unwrap_or(false);
+            let _ = sender.send(Some(outcome(started)));
+        });
+    }
+
+    /// Time given to the processes of `program` to be `Running` once spawned. Every attempt to
+    /// start gets the readiness `timeout`, or `starttime` when it has none.
+    fn start_deadline(program: &Program) -> Duration {
+        let attempts = *program.start_retries() + 1;
+        let attempt = program
+            .readiness()
+            .timeout()
+            .unwrap_or(*program.start_time());
+        Duration::from_secs(u64::from(attempt) * u64::from(attempts)) + START_WAIT_MARGIN
+    }
+
+    /// Responds from another task once every process of `target` is `Running`, or with `false`
+    /// once one is not running anymore or after `timeout` seconds, `start_deadline` by default.
+    fn wait(&self, target: &str, timeout: Option<u32>, sender: oneshot::Sender<Option<bool>>) {
+        let Some((program, instance)) = self.parse_target(target) else {
+            let _ = sender.send(None);
+            return;
+        };
+        let expected = if instance.is_some() {
+            1
+        } else {
+            *program.num_procs() as usize
+        };
+        let deadline = timeout.map_or_else(
+            || Self::start_deadline(program),
+            |timeout| Duration::from_secs(u64::from(timeout)),
+        );
+        let readiness: Vec<_> = self
+            .find_processes(target)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|handle| !handle.stopping && !handle.is_over())
+            .map(process_handler::Handle::wait_ready)
+            .collect();
+        if readiness.len() < expected {
+            let _ = sender.send(Some(false));
+            return;
+        }
+
+        tokio::spawn(async move {
+            let wait = async {
+                for ready in readiness {
+                    if !ready.await {
+                        return false;
+                    }
+                }
+                true
+            };
+            let ready = tokio::time::timeout(deadline, wait).await.unwrap_or(false);
+            let _ = sender.send(Some(ready));
+        });
+    }
+
+    /// Stops the processes of `target`, and responds from another task once they have all
+    /// exited. Their handles are kept until then, so that they are not started twice.
+    fn stop(
+        &mut self,
+        target: &str,
+        timeout: Option<u32>,
+        force: bool,
+        sender: oneshot::Sender<bool>,
+    ) {
+        let Some((program, instance)) = self.parse_target(target) else {
+            let _ = sender.send(false);
+            return;
+        };
+        let name = program.name().clone();
+        let kill_command_senders: Vec<_> = self
+            .processes
+            .get_mut(&name)
+            .into_iter()
+            .flatten()
+            .filter(|handle| instance.is_none_or(|instance| handle.instance == instance))
+            .map(|handle| {
+                handle.stopping = true;
+                handle.kill_command_sender.clone()
+            })
+            .collect();
+
+        let mut stops = JoinSet::new();
+        for kill_command_sender in kill_command_senders {
+            stops.spawn(async move {
+                let (state_sender, state_receiver) = oneshot::channel();
+                let command = KillCommand {
+                    timeout,
+                    force,
+                    sender: state_sender,
+                };
+                // Fails if the routine is already over
+                if kill_command_sender.send(command).await.is_ok() {
+                    let _ = state_receiver.await;
+                }
+                kill_command_sender.closed().await;
+            });
+        }
+        tokio::spawn(async move {
+            stops.join_all().await;
+            let _ = sender.send(true);
+        });
+    }
+
+    /// Merges the buffered output of every process of `target` and keeps the last `lines`.
+    fn tail(&self, target: &str, lines: usize, stream: Option<LogStream>) -> Option<Vec<LogLine>> {
+        let stream = stream.map(LogType::from);
+        let mut logs: Vec<_> = self
+            .find_processes(target)?
+            .into_iter()
+            .flat_map(|handle| handle.log_buffer.tail(lines, stream.as_ref()))
+            .collect();
+        logs.sort_by_key(|log| log.timestamp);
+        let skipped = logs.len().saturating_sub(lines);
+
+        Some(logs.into_iter().skip(skipped).map(LogLine::from).collect())
+    }
+
+    /// Sends `signal` to every process of `target` and reports how it went for each of them.
+    fn signal(
+        &self,
+        target: &str,
+        signal: Signal,
+        process_group: bool,
+    ) -> Option<Vec<SignalResult>> {
+        let (program, _) = self.parse_target(target)?;
+        let program = program.name();
+        let results = self
+            .find_processes(target)?
+            .into_iter()
+            .map(|handle| {
+                let error = match handle.signal(signal, process_group) {
+                    None => Some("not running".to_string()),
+                    Some(result) => result.err().map(|err| err.to_string()),
+                };
+                SignalResult {
+                    program: program.clone(),
+                    instance: handle.instance,
+                    error,
+                }
+            })
+            .collect();
+
+        Some(results)
+    }
+
+    /// Forwards the logs of every process of `targets` to the returned receiver, until it is
+    /// dropped.
+    fn follow_logs(
+        &self,
+        targets: &[String],
+        streams: Vec<LogStream>,
+    ) -> Result<LogLineReceiver, String> {
+        let mut handles = Vec::new();
+        for target in targets {
+            handles.extend(self.find_processes(target).ok_or_else(|| target.clone())?);
+        }
+        let streams: Arc<[LogType]> = streams.into_iter().map(LogType::from).collect();
+
+        let (sender, receiver) = mpsc::channel(LOG_LINE_CHANNEL_CAPACITY);
+        for mut logs in handles.iter().filter_map(|handle| handle.subscribe_logs()) {
+            let sender = sender.clone();
+            let streams = Arc::clone(&streams);
+            tokio::spawn(async move {
+                while let Some(log) = logs.recv().await {
+                    let followed = streams.is_empty()
+                        || streams.iter().any(|stream| log.log_type.belongs_to(stream));
+                    if followed && sender.send(LogLine::from(log)).await.is_err() {
+                        break;
+                    }
+                }
+                if logs.dropped() > 0 {
+                    eprintln!(
+                        "Taskmaster error: {} logs were dropped for a client too slow to follow them",
+                        logs.dropped()
+                    );
+                }
+            });
+        }
+
+        Ok(receiver)
+    }
+
+    /// Publishes the status transitions and the trigger events of a freshly spawned process, for
+    /// as long as it is supervised. `ready` is sent once the process is `Running`, and dropped if
+    /// the routine ends before that.
+    fn publish_process_events(
+        events: &Hub<Event>,
+        program: &str,
+        handle: &mut process_handler::Handle,
+        mut ready: Option<oneshot::Sender<()>>,
+    ) {
+        let instance = handle.instance;
+
+        // The receiver of the handle got every status since the process was spawned, unlike a
+        // new subscription. It is left closed in the handle, where nobody would drain it.
+        let (_, closed) = mpsc::unbounded_channel();
+        let mut statuses = std::mem::replace(&mut handle.status_receiver, closed);
+        let status_events = events.clone();
+        let program = program.to_string();
+        tokio::spawn(async move {
+            while let Some(status) = statuses.recv().await {
+                if matches!(status, Status::Running)
+                    && let Some(ready) = ready.take()
+                {
+                    let _ = ready.send(());
                 }
+                status_events.send(Event::Status {
+                    program: program.clone(),
+                    instance,
+                    status: status.into(),
+                });
+            }
+        });
+
+        if let Some(mut trigger_events) = handle.subscribe_events() {
+            let events = events.clone();
+            tokio::spawn(async move {
+                while let Some(event) = trigger_events.recv().await {
+                    events.send(event.into());
+                }
+            });
+        }
+    }
+
+    /// Forwards the events selected by `filter` to the returned receiver, until it is dropped.
+    fn subscribe(&self, filter: EventFilter) -> EventReceiver {
+        let mut events = self.events.subscribe();
+        let (sender, receiver) = mpsc::channel(EVENT_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                if filter.matches(&event) && sender.send(event).await.is_err() {
+                    break;
+                }
+            }
+            if events.dropped() > 0 {
+                eprintln!(
+                    "Taskmaster error: {} events were dropped for a client too slow to receive them",
+                    events.dropped()
+                );
+            }
+        });
+
+        receiver
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+    use commands::ProcessStatus;
+    use std::io::Cursor;
+
+    fn programs(yaml: &str) -> Vec<Program> {
+        let mut programs = Config::from_reader(Cursor::new(yaml)).unwrap().programs;
+        programs.sort_by(|a, b| a.name().cmp(b.name()));
+        programs
+    }
+
+    #[test]
+    fn test_dependents() {
+        let tasks = programs(
+            r#"programs:
+    web:
+        cmd: "true"
+        autostart: true
+        depends_on: [api]
+    api:
+        cmd: "true"
+        autostart: true
+        depends_on: [database]
+    database:
+        cmd: "true"
+        autostart: true
+    ping:
+        cmd: "true"
+        autostart: true
+        depends_on: [pong]
+    pong:
+        cmd: "true"
+        autostart: true
+        depends_on: [ping]"#,
+        );
+
+        let dependents = Routine::dependents(&tasks);
+        let names: Vec<_> = dependents.iter().map(|program| program.name()).collect();
+        // The programs depending on each other are never started
+        assert_eq!(names, ["api", "web"]);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_dependencies() {
+        let tasks = programs(
+            r#"programs:
+    database:
+        cmd: "sleep 30"
+        autostart: true
+        starttime: 1
+    web:
+        cmd: "sleep 30"
+        autostart: true
+        depends_on: [database]
+    worker:
+        cmd: "sleep 30""#,
+        );
+
+        let manager = Routine::spawn(tasks).await;
+        // Not started while the database is not `Running` yet
+        assert_eq!(
+            manager.wait("web".to_string(), Some(5)).await.unwrap(),
+            Some(false)
+        );
+        assert_eq!(
+            manager.wait("database".to_string(), None).await.unwrap(),
+            Some(true)
+        );
+        // Started right after, by another task
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while manager.wait("web".to_string(), Some(1)).await.unwrap() != Some(true) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("the web server was not started");
+        // Not running at all
+        assert_eq!(
+            manager.wait("worker".to_string(), Some(5)).await.unwrap(),
+            Some(false)
+        );
+        assert_eq!(manager.wait("nginx".to_string(), None).await.unwrap(), None);
+
+        manager
+            .stop("database".to_string(), None, true)
+            .await
+            .unwrap();
+        manager.stop("web".to_string(), None, true).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_publish_process_events() {
+        let program = Config::from_reader(Cursor::new(
+            r#"programs:
+    web:
+        cmd: "bash -c \"echo pool exhausted; sleep 0.5\""
+        triggers:
+          - pattern: "pool"
+            action: event"#,
+        ))
+        .unwrap()
+        .programs
+        .remove(0);
+        let events = Hub::new(EVENT_HUB_CAPACITY);
+        let mut subscriber = events.subscribe();
+
+        let mut handle = process_handler::Routine::spawn(program, 0).await.unwrap();
+        let (ready_sender, ready_receiver) = oneshot::channel();
+        Routine::publish_process_events(&events, "web", &mut handle, Some(ready_sender));
+        ready_receiver.await.unwrap();
+
+        let mut statuses = Vec::new();
+        let mut triggers = Vec::new();
+        while !matches!(statuses.last(), Some(ProcessStatus::Exited { .. })) {
+            let event = tokio::time::timeout(Duration::from_secs(5), subscriber.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            match event {
+                Event::Status {
+                    program,
+                    instance,
+                    status,
+                } => {
+                    assert_eq!((program.as_str(), instance), ("web", 0));
+                    statuses.push(status);
+                }
+                event => triggers.push(event),
             }
         }
+
+        assert_eq!(
+            statuses,
+            vec![
+                ProcessStatus::Starting,
+                ProcessStatus::Running,
+                ProcessStatus::Exited {
+                    exit_code: Some(0),
+                    signal: None
+                }
+            ]
+        );
+        assert_eq!(
+            triggers,
+            vec![Event::Trigger {
+                program: "web".to_string(),
+                instance: 0,
+                pattern: "pool".to_string(),
+                line: "pool exhausted".to_string(),
+            }]
+        );
     }
 }
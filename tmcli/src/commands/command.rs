@@ -1,18 +1,61 @@
 use crate::Session;
 use crate::commands::placeholder::*;
+use crate::commands::{CommandExecutionError, control, signal, tail};
+use commands::Feature;
 
 #[derive(Debug)]
 pub enum Command {
     ListTasks,
-    StartProgram(String),
-    StopProgram(String),
+    StartProgram {
+        target: String,
+        wait: bool,
+    },
+    WaitProgram {
+        target: String,
+        timeout: Option<u32>,
+    },
+    StopProgram {
+        target: String,
+        timeout: Option<u32>,
+        kill: bool,
+    },
     RestartProgram(String),
     ReloadConfigFile,
+    ReopenLogFiles,
+    Tail {
+        target: String,
+        follow: bool,
+    },
+    Signal {
+        signal: String,
+        target: String,
+        process_group: bool,
+    },
     StopDaemon,
 }
 
 impl Command {
-    pub async fn send(&self, _conn: &Session) -> Result<(), PlaceHolderError> {
+    /// Feature the server has to advertise for the command to be sent.
+    fn required_feature(&self) -> Option<Feature> {
+        match self {
+            Command::StartProgram { .. } | Command::StopProgram { .. } => Some(Feature::StartStop),
+            Command::RestartProgram(_) => Some(Feature::Restart),
+            Command::ReopenLogFiles => Some(Feature::ReopenLogs),
+            Command::Tail { follow: true, .. } => Some(Feature::FollowLogs),
+            Command::Tail { follow: false, .. } => Some(Feature::Tail),
+            Command::Signal { .. } => Some(Feature::Signal),
+            Command::WaitProgram { .. } => Some(Feature::Wait),
+            Command::ListTasks | Command::ReloadConfigFile | Command::StopDaemon => None,
+        }
+    }
+
+    pub async fn send(&self, _conn: &mut Session) -> Result<(), CommandExecutionError> {
+        if let Some(feature) = self.required_feature()
+            && !_conn.server.features.contains(&feature)
+        {
+            return Err(CommandExecutionError::UnsupportedFeature(feature));
+        }
+
         match self {
             Command::ListTasks => {
                 list_tasks()
@@ -21,11 +64,18 @@ impl Command {
                     .into_iter()
                     .for_each(|item| println!("\t{item}"));
             }
-            Command::StartProgram(task) => {
-                start(task.to_owned()).call(_conn).await?.unwrap(); //TODO: check value at unwrap
+            Command::StartProgram { target, wait } => {
+                control::start(_conn, target.to_owned(), *wait).await?;
+            }
+            Command::WaitProgram { target, timeout } => {
+                control::wait(_conn, target.to_owned(), *timeout).await?;
             }
-            Command::StopProgram(task) => {
-                stop(task.to_owned()).call(_conn).await?.unwrap(); //TODO: check value at unwrap
+            Command::StopProgram {
+                target,
+                timeout,
+                kill,
+            } => {
+                control::stop(_conn, target.to_owned(), *timeout, *kill).await?;
             }
             Command::RestartProgram(task) => {
                 restart(task.to_owned()).call(_conn).await?.unwrap(); //TODO: check value at unwrap
@@ -33,6 +83,23 @@ impl Command {
             Command::ReloadConfigFile => {
                 reload().call(_conn).await?.unwrap(); //TODO: check value at unwrap
             }
+            Command::ReopenLogFiles => {
+                reopen_logs().call(_conn).await?.unwrap(); //TODO: check value at unwrap
+            }
+            Command::Tail { target, follow } => {
+                if *follow {
+                    tail::follow(_conn, target.to_owned()).await?;
+                } else {
+                    tail::tail(_conn, target.to_owned()).await?;
+                }
+            }
+            Command::Signal {
+                signal,
+                target,
+                process_group,
+            } => {
+                signal::signal(_conn, signal.to_owned(), target.to_owned(), *process_group).await?;
+            }
             Command::StopDaemon => {
                 shutdown().call(_conn).await?.unwrap(); //TODO: check value at unwrap
             }
@@ -0,0 +1,66 @@
+use commands::{ClientCommand, ServerCommand};
+
+use crate::{
+    commands::{CommandExecutionError, unexpected_response},
+    session::Session,
+};
+
+/// Starts the processes of `target`, and waits for them to be running with `wait`.
+pub async fn start(
+    session: &mut Session,
+    target: String,
+    wait: bool,
+) -> Result<(), CommandExecutionError> {
+    let id = session.send(ServerCommand::Start { target, wait }).await?;
+
+    match session.read_response(id).await? {
+        Some(ClientCommand::Started { target }) => {
+            println!("{target}: started");
+            Ok(())
+        }
+        response => Err(unexpected_response(response)),
+    }
+}
+
+/// Waits for the processes of `target` to be running, for at most `timeout` seconds.
+pub async fn wait(
+    session: &mut Session,
+    target: String,
+    timeout: Option<u32>,
+) -> Result<(), CommandExecutionError> {
+    let id = session
+        .send(ServerCommand::Wait { target, timeout })
+        .await?;
+
+    match session.read_response(id).await? {
+        Some(ClientCommand::Ready { target }) => {
+            println!("{target}: ready");
+            Ok(())
+        }
+        response => Err(unexpected_response(response)),
+    }
+}
+
+/// Stops the processes of `target` and waits for them to exit.
+pub async fn stop(
+    session: &mut Session,
+    target: String,
+    timeout: Option<u32>,
+    kill: bool,
+) -> Result<(), CommandExecutionError> {
+    let id = session
+        .send(ServerCommand::Stop {
+            target,
+            timeout,
+            kill,
+        })
+        .await?;
+
+    match session.read_response(id).await? {
+        Some(ClientCommand::Stopped { target }) => {
+            println!("{target}: stopped");
+            Ok(())
+        }
+        response => Err(unexpected_response(response)),
+    }
+}
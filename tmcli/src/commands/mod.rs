@@ -1,10 +1,14 @@
 mod command;
+mod control;
 pub mod oneshot_command;
 pub mod parsing;
 // TODO remove this
 mod placeholder;
+mod signal;
+mod tail;
 
 use command::Command;
+use std::process::ExitCode;
 #[allow(unused_imports)]
 use thiserror::Error;
 
@@ -13,17 +17,67 @@ use crate::{commands::placeholder::PlaceHolderError, session::Session};
 #[derive(Error, Debug)]
 #[allow(dead_code)]
 pub enum CommandExecutionError {
-    #[error("No such program: `{0}`")]
-    NoSuchProgram(String),
     #[error("`{0}`")]
     RequestError(#[from] connection::Error),
     #[error("PlaceHolder error: `{0}`")]
-    PlaceHolderError(PlaceHolderError),
+    PlaceHolderError(#[from] PlaceHolderError),
+    #[error("Unexpected response from server: `{0:?}`")]
+    UnexpectedResponse(commands::ClientCommand),
+    #[error("Connection closed by server")]
+    ConnectionClosed,
+    #[error("The signal could not be delivered to {0} process(es)")]
+    SignalNotDelivered(usize),
+    #[error("The server does not support `{0:?}` commands")]
+    UnsupportedFeature(commands::Feature),
+    /// The server replied with an error frame
+    #[error("{message}")]
+    Server {
+        kind: commands::ErrorKind,
+        message: String,
+    },
 }
 
-pub async fn send_command(cmd: Command, session: &Session) -> Result<(), CommandExecutionError> {
-    cmd.send(session)
-        .await
-        .map_err(CommandExecutionError::PlaceHolderError)?;
-    Ok(())
+impl CommandExecutionError {
+    /// Lets scripts tell the failures apart without parsing the message.
+    pub fn exit_code(&self) -> ExitCode {
+        use commands::ErrorKind;
+
+        let code = match self {
+            Self::Server { kind, .. } => match kind {
+                ErrorKind::NoSuchProgram => 3,
+                ErrorKind::AlreadyRunning => 4,
+                ErrorKind::StartFailed => 5,
+                ErrorKind::InvalidSignal => 6,
+                ErrorKind::PermissionDenied => 7,
+                ErrorKind::UnsupportedCommand => 8,
+                ErrorKind::NotReady => 11,
+                ErrorKind::DuplicateRequest | ErrorKind::Internal | ErrorKind::Unknown => 1,
+            },
+            Self::UnsupportedFeature(_) => 8,
+            Self::SignalNotDelivered(_) => 9,
+            Self::RequestError(_) | Self::ConnectionClosed => 10,
+            Self::PlaceHolderError(_) | Self::UnexpectedResponse(_) => 1,
+        };
+        ExitCode::from(code)
+    }
+}
+
+pub async fn send_command(
+    cmd: Command,
+    session: &mut Session,
+) -> Result<(), CommandExecutionError> {
+    cmd.send(session).await
+}
+
+/// Turns a response the command did not expect into the matching error.
+fn unexpected_response(response: Option<commands::ClientCommand>) -> CommandExecutionError {
+    use commands::ClientCommand;
+
+    match response {
+        Some(ClientCommand::Error { kind, message, .. }) => {
+            CommandExecutionError::Server { kind, message }
+        }
+        Some(response) => CommandExecutionError::UnexpectedResponse(response),
+        None => CommandExecutionError::ConnectionClosed,
+    }
 }
@@ -6,6 +6,7 @@ use crate::{
         send_command,
     },
 };
+use std::process::ExitCode;
 
 #[derive(Debug, thiserror::Error)]
 enum Error {
@@ -17,14 +18,24 @@ enum Error {
     EmptyCommand,
 }
 
-pub async fn run(session: Session) -> Result<(), ()> {
-    let Some(command) =
-        parse_command(std::env::args().skip(1)).map_err(|err| eprintln!("{err}"))?
-    else {
+/// Exit code of a command line that could not be parsed
+const USAGE: u8 = 2;
+
+pub async fn run(
+    session: &mut Session,
+    args: impl Iterator<Item = String>,
+) -> Result<(), ExitCode> {
+    let command = parse_command(args).map_err(|err| {
+        eprintln!("{}", Error::Parsing(err));
+        ExitCode::from(USAGE)
+    })?;
+    let Some(command) = command else {
         eprintln!("{}", Error::EmptyCommand);
-        return Err(());
+        return Err(ExitCode::from(USAGE));
     };
-    send_command(command, &session)
-        .await
-        .map_err(|err| eprintln!("{err}"))
+    send_command(command, session).await.map_err(|err| {
+        let code = err.exit_code();
+        eprintln!("{}", Error::Execution(err));
+        code
+    })
 }
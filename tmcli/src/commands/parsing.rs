@@ -7,15 +7,23 @@ pub enum ParseError {
     #[error(
         "Bad command name: `{command}`\naccepted command names are :\n\
             \tstatus\n\
-            \tstop\n\
-            \tstart\n\
-            \trestart\n\
+            \tstop [--timeout <secs>] [--kill] <target>\n\
+            \tstart [--wait] <target>\n\
+            \twait [--timeout <secs>] <target>\n\
+            \trestart <target>\n\
             \tshutdown\n\
-            \treload"
+            \treload\n\
+            \treopen\n\
+            \ttail [-f] <target>\n\
+            \tsignal [-g] <signal> <target>"
     )]
     BadCommand { command: String },
     #[error("Missing argument")]
     MissingArgument,
+    #[error("Invalid timeout: `{input}`, expected a number of seconds")]
+    InvalidTimeout { input: String },
+    #[error("Unknown option: `{option}`")]
+    UnknownOption { option: String },
 }
 
 pub fn parse_command(
@@ -24,13 +32,69 @@ pub fn parse_command(
     match args.next().ok_or(ParseError::MissingArgument)?.trim() {
         "status" => Ok(Some(Command::ListTasks)),
         "start" => {
-            let program = args.next().ok_or(ParseError::MissingArgument)?;
-            //TODO: args handling
-            Ok(Some(Command::StartProgram(program)))
+            let mut wait = false;
+            let target = loop {
+                match args.next().ok_or(ParseError::MissingArgument)?.as_str() {
+                    "--wait" => wait = true,
+                    option if option.starts_with("--") => {
+                        return Err(ParseError::UnknownOption {
+                            option: option.to_string(),
+                        });
+                    }
+                    target => break target.to_string(),
+                }
+            };
+            Ok(Some(Command::StartProgram { target, wait }))
+        }
+        "wait" => {
+            let mut timeout = None;
+            let target = loop {
+                match args.next().ok_or(ParseError::MissingArgument)?.as_str() {
+                    "--timeout" => {
+                        let input = args.next().ok_or(ParseError::MissingArgument)?;
+                        timeout = Some(
+                            input
+                                .parse()
+                                .map_err(|_| ParseError::InvalidTimeout { input })?,
+                        );
+                    }
+                    option if option.starts_with("--") => {
+                        return Err(ParseError::UnknownOption {
+                            option: option.to_string(),
+                        });
+                    }
+                    target => break target.to_string(),
+                }
+            };
+            Ok(Some(Command::WaitProgram { target, timeout }))
         }
         "stop" => {
-            let program = args.next().ok_or(ParseError::MissingArgument)?;
-            Ok(Some(Command::StopProgram(program)))
+            let mut timeout = None;
+            let mut kill = false;
+            let target = loop {
+                match args.next().ok_or(ParseError::MissingArgument)?.as_str() {
+                    "--timeout" => {
+                        let input = args.next().ok_or(ParseError::MissingArgument)?;
+                        timeout = Some(
+                            input
+                                .parse()
+                                .map_err(|_| ParseError::InvalidTimeout { input })?,
+                        );
+                    }
+                    "--kill" => kill = true,
+                    option if option.starts_with("--") => {
+                        return Err(ParseError::UnknownOption {
+                            option: option.to_string(),
+                        });
+                    }
+                    target => break target.to_string(),
+                }
+            };
+            Ok(Some(Command::StopProgram {
+                target,
+                timeout,
+                kill,
+            }))
         }
         "restart" => {
             let program = args.next().ok_or(ParseError::MissingArgument)?;
@@ -38,6 +102,28 @@ pub fn parse_command(
         }
         "shutdown" => Ok(Some(Command::StopDaemon)),
         "reload" => Ok(Some(Command::ReloadConfigFile)),
+        "reopen" => Ok(Some(Command::ReopenLogFiles)),
+        "tail" => {
+            let mut target = args.next().ok_or(ParseError::MissingArgument)?;
+            let follow = target == "-f";
+            if follow {
+                target = args.next().ok_or(ParseError::MissingArgument)?;
+            }
+            Ok(Some(Command::Tail { target, follow }))
+        }
+        "signal" => {
+            let mut signal = args.next().ok_or(ParseError::MissingArgument)?;
+            let process_group = signal == "-g";
+            if process_group {
+                signal = args.next().ok_or(ParseError::MissingArgument)?;
+            }
+            let target = args.next().ok_or(ParseError::MissingArgument)?;
+            Ok(Some(Command::Signal {
+                signal,
+                target,
+                process_group,
+            }))
+        }
         "" => Ok(None),
         command => Err(ParseError::BadCommand {
             command: command.to_string(),
@@ -33,22 +33,17 @@ pub fn list_tasks() -> PlaceHolder<Vec<String>> {
 }
 
 // #[rpc_genie::rpc]
-pub fn start(_task: String) -> PlaceHolder<Result<(), PlaceHolderError>> {
-    PlaceHolder::__new(Ok(()))
-}
-
-// #[rpc_genie::rpc]
-pub fn stop(_task: String) -> PlaceHolder<Result<(), PlaceHolderError>> {
+pub fn restart(_task: String) -> PlaceHolder<Result<(), PlaceHolderError>> {
     PlaceHolder::__new(Ok(()))
 }
 
 // #[rpc_genie::rpc]
-pub fn restart(_task: String) -> PlaceHolder<Result<(), PlaceHolderError>> {
+pub fn reload() -> PlaceHolder<Result<(), PlaceHolderError>> {
     PlaceHolder::__new(Ok(()))
 }
 
 // #[rpc_genie::rpc]
-pub fn reload() -> PlaceHolder<Result<(), PlaceHolderError>> {
+pub fn reopen_logs() -> PlaceHolder<Result<(), PlaceHolderError>> {
     PlaceHolder::__new(Ok(()))
 }
 
@@ -0,0 +1,43 @@
+use commands::{ClientCommand, ServerCommand};
+
+use crate::{
+    commands::{CommandExecutionError, unexpected_response},
+    session::Session,
+};
+
+/// Sends `signal` to every running process of `target` and prints the outcome for each of them.
+pub async fn signal(
+    session: &mut Session,
+    signal: String,
+    target: String,
+    process_group: bool,
+) -> Result<(), CommandExecutionError> {
+    let id = session
+        .send(ServerCommand::Signal {
+            target,
+            signal,
+            process_group,
+        })
+        .await?;
+
+    let results = match session.read_response(id).await? {
+        Some(ClientCommand::SignalSent(results)) => results,
+        response => return Err(unexpected_response(response)),
+    };
+
+    let mut failures = 0;
+    for result in results {
+        match result.error {
+            None => println!("{}:{}: signal sent", result.program, result.instance),
+            Some(error) => {
+                eprintln!("{}:{}: {error}", result.program, result.instance);
+                failures += 1;
+            }
+        }
+    }
+
+    match failures {
+        0 => Ok(()),
+        failures => Err(CommandExecutionError::SignalNotDelivered(failures)),
+    }
+}
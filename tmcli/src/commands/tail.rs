@@ -0,0 +1,56 @@
+use commands::{ClientCommand, ServerCommand};
+
+use crate::{
+    commands::{CommandExecutionError, unexpected_response},
+    session::Session,
+};
+
+/// Number of lines printed by `tail` without `-f`
+const TAIL_LINES: usize = 10;
+
+/// Prints the last lines of output of `target`.
+pub async fn tail(session: &mut Session, target: String) -> Result<(), CommandExecutionError> {
+    let id = session
+        .send(ServerCommand::Tail {
+            target,
+            lines: TAIL_LINES,
+            stream: None,
+        })
+        .await?;
+
+    match session.read_response(id).await? {
+        Some(ClientCommand::LogLines(log_lines)) => {
+            log_lines
+                .into_iter()
+                .for_each(|log_line| print!("{}", log_line.message));
+            Ok(())
+        }
+        response => Err(unexpected_response(response)),
+    }
+}
+
+/// Prints the output of `target` as it is produced, until Ctrl-C or until the program is gone.
+pub async fn follow(session: &mut Session, target: String) -> Result<(), CommandExecutionError> {
+    let id = session
+        .send(ServerCommand::FollowLogs {
+            targets: vec![target],
+            streams: Vec::new(),
+        })
+        .await?;
+
+    let mut unfollowed = false;
+    loop {
+        tokio::select! {
+            frame = session.read_response(id) => match frame? {
+                Some(ClientCommand::LogLine(log_line)) => print!("{}", log_line.message),
+                Some(ClientCommand::LogStreamEnded) => return Ok(()),
+                response => return Err(unexpected_response(response)),
+            },
+            _ = tokio::signal::ctrl_c(), if !unfollowed => {
+                // Keep printing the lines already sent until the server ends the stream
+                session.send(ServerCommand::UnfollowLogs { id }).await?;
+                unfollowed = true;
+            }
+        }
+    }
+}
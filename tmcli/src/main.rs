@@ -1,13 +1,23 @@
 mod commands;
 mod session;
 mod shell;
+mod tls;
 
-use crate::session::Session;
-use std::process::ExitCode;
+use crate::session::{Address, ConnectOptions, Session};
+use std::{iter::Peekable, process::ExitCode};
 
 #[tokio::main]
 async fn main() -> ExitCode {
-    let session = match Session::new().await {
+    let mut args = std::env::args().skip(1).peekable();
+    let options = match parse_options(&mut args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut session = match Session::new(&options).await {
         Ok(session) => session,
         Err(err) => {
             eprintln!("Failed to instanciate connection: {err}");
@@ -15,15 +25,45 @@ async fn main() -> ExitCode {
         }
     };
 
-    if std::env::args().nth(1).is_some() {
-        match commands::oneshot_command::run(session).await {
-            Ok(()) => ExitCode::SUCCESS,
-            Err(()) => ExitCode::FAILURE,
-        }
+    let result = if args.peek().is_some() {
+        commands::oneshot_command::run(&mut session, args).await
     } else {
-        match shell::run(session).await {
-            Ok(()) => ExitCode::SUCCESS,
-            Err(()) => ExitCode::FAILURE,
+        shell::run(&mut session)
+            .await
+            .map_err(|()| ExitCode::FAILURE)
+    };
+    let _ = session.connection.shutdown().await;
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(code) => code,
+    }
+}
+
+fn parse_options(
+    args: &mut Peekable<impl Iterator<Item = String>>,
+) -> Result<ConnectOptions, String> {
+    let mut options = ConnectOptions::default();
+
+    while let Some(option) = args.next_if(|arg| arg.starts_with("--")) {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value after {option}"))?;
+        match option.as_str() {
+            "--address" => options.address = Address::Tcp(value),
+            "--socket" => options.address = Address::Unix(value.into()),
+            "--secret-file" => options.secret_file = Some(value.into()),
+            "--ca" => options.ca_file = Some(value.into()),
+            "--cert" => options.cert_file = Some(value.into()),
+            "--key" => options.key_file = Some(value.into()),
+            _ => return Err(format!("Unknown option: {option}")),
         }
     }
+
+    // Without an authority to check the server against, the connection would not use TLS at all
+    if options.ca_file.is_none() && (options.cert_file.is_some() || options.key_file.is_some()) {
+        return Err("--cert and --key require --ca to enable TLS".to_string());
+    }
+
+    Ok(options)
 }
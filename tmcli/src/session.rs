@@ -1,10 +1,51 @@
-use commands::{ClientCommand, ServerCommand};
-use connection::Connection;
-use std::io;
-use tokio::net::TcpStream;
+use crate::tls::{self, TlsError};
+use commands::{
+    ClientCommand, Hello, PROTOCOL_VERSION, Request, RequestId, Response, ServerCommand, auth,
+};
+use connection::{Codec, Connection, MessagePack};
+use std::{io, path::PathBuf};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    net::{TcpStream, UnixStream},
+};
+
+pub const DEFAULT_ADDRESS: &str = "localhost:4444";
+
+/// Where the Taskmaster server listens.
+#[derive(Debug, Clone)]
+pub enum Address {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl Default for Address {
+    fn default() -> Self {
+        Self::Tcp(DEFAULT_ADDRESS.to_string())
+    }
+}
+
+/// How to reach the server and prove who we are, from the command line options.
+#[derive(Debug, Default)]
+pub struct ConnectOptions {
+    pub address: Address,
+    /// Secret answering the authentication challenge of the server
+    pub secret_file: Option<PathBuf>,
+    /// Authorities the server certificate is checked against, enables TLS
+    pub ca_file: Option<PathBuf>,
+    /// Client certificate, for servers verifying them
+    pub cert_file: Option<PathBuf>,
+    pub key_file: Option<PathBuf>,
+}
+
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
 
 pub struct Session {
-    pub _stream: Connection<TcpStream, ClientCommand, ServerCommand>,
+    pub connection: Connection<Box<dyn Stream>, Response, Request>,
+    /// Greeting of the server, telling which commands it handles
+    pub server: Hello,
+    last_id: RequestId,
 }
 
 use thiserror::Error;
@@ -13,15 +54,127 @@ use thiserror::Error;
 pub enum ConnectError {
     #[error("Failed to connect to Taskmaster server")]
     ConnectionFailure(#[from] io::Error),
+    #[error("Failed to read the secret file: {0}")]
+    ReadSecret(io::Error),
+    #[error("Failed to set up TLS: {0}")]
+    Tls(#[from] TlsError),
+    #[error("TLS is only supported over tcp")]
+    TlsOverUnixSocket,
+    #[error("Failed to read greeting from Taskmaster server: {0}")]
+    Greeting(connection::Error),
+    #[error("Unexpected greeting from Taskmaster server: {0:?}")]
+    UnexpectedGreeting(Option<ClientCommand>),
+    #[error("Taskmaster server requires authentication, use --secret-file")]
+    MissingSecret,
+    #[error("Failed to authenticate to Taskmaster server")]
+    AuthenticationFailed,
+    #[error(
+        "Taskmaster server {} speaks protocol version {}, this client only speaks version {PROTOCOL_VERSION}",
+        .0.server_version,
+        .0.protocol_version
+    )]
+    IncompatibleServer(Hello),
 }
 
 impl Session {
-    pub async fn new() -> Result<Self, ConnectError> {
-        let socket = TcpStream::connect("localhost:4444")
-            .await
-            .map_err(ConnectError::ConnectionFailure)?;
-        Ok(Self {
-            _stream: Connection::new(socket, 1024),
-        })
+    /// Connects to the server, answering its authentication challenge with the secret if it
+    /// sends one.
+    pub async fn new(options: &ConnectOptions) -> Result<Self, ConnectError> {
+        let secret = options
+            .secret_file
+            .as_ref()
+            .map(auth::read_secret)
+            .transpose()
+            .map_err(ConnectError::ReadSecret)?;
+        let tls_connector = options
+            .ca_file
+            .as_deref()
+            .map(|ca_file| {
+                tls::connector(
+                    ca_file,
+                    options.cert_file.as_deref(),
+                    options.key_file.as_deref(),
+                )
+            })
+            .transpose()?;
+
+        let mut socket: Box<dyn Stream> = match (&options.address, tls_connector) {
+            (Address::Tcp(addr), None) => Box::new(TcpStream::connect(addr).await?),
+            (Address::Tcp(addr), Some(connector)) => {
+                let server_name = tls::server_name(addr)?;
+                let socket = TcpStream::connect(addr).await?;
+                Box::new(connector.connect(server_name, socket).await?)
+            }
+            (Address::Unix(path), None) => Box::new(UnixStream::connect(path).await?),
+            (Address::Unix(_), Some(_)) => return Err(ConnectError::TlsOverUnixSocket),
+        };
+        socket
+            .write_all(format!("{}\n", MessagePack::NAME).as_bytes())
+            .await?;
+        let mut connection = Connection::new(socket, 1024);
+
+        let mut greeting = read_greeting(&mut connection).await?;
+        if let Some(ClientCommand::AuthChallenge { nonce }) = greeting {
+            let secret = secret.as_deref().ok_or(ConnectError::MissingSecret)?;
+            connection
+                .write_frame(&Request {
+                    id: 0,
+                    command: ServerCommand::Authenticate {
+                        signature: auth::sign_challenge(secret, &nonce),
+                    },
+                })
+                .await
+                .map_err(ConnectError::Greeting)?;
+            greeting = read_greeting(&mut connection).await?;
+        }
+
+        match greeting {
+            Some(ClientCommand::Hello(server)) if server.is_compatible() => Ok(Self {
+                connection,
+                server,
+                last_id: 0,
+            }),
+            Some(ClientCommand::Hello(server)) => Err(ConnectError::IncompatibleServer(server)),
+            Some(ClientCommand::AuthenticationFailed) => Err(ConnectError::AuthenticationFailed),
+            greeting => Err(ConnectError::UnexpectedGreeting(greeting)),
+        }
+    }
+
+    /// Sends `command` in a new request and returns the id of its responses.
+    pub async fn send(&mut self, command: ServerCommand) -> connection::Result<RequestId> {
+        self.last_id += 1;
+        self.connection
+            .write_frame(&Request {
+                id: self.last_id,
+                command,
+            })
+            .await?;
+        Ok(self.last_id)
     }
+
+    /// Reads the next response to request `id`, skipping the late ones to previous requests.
+    pub async fn read_response(
+        &mut self,
+        id: RequestId,
+    ) -> connection::Result<Option<ClientCommand>> {
+        loop {
+            match self.connection.read_frame().await? {
+                Some(response) if response.id.is_none_or(|response_id| response_id == id) => {
+                    return Ok(Some(response.command));
+                }
+                Some(_) => continue,
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+async fn read_greeting(
+    connection: &mut Connection<Box<dyn Stream>, Response, Request>,
+) -> Result<Option<ClientCommand>, ConnectError> {
+    let greeting = connection
+        .read_frame()
+        .await
+        .map_err(ConnectError::Greeting)?;
+    Ok(greeting.map(|response| response.command))
 }
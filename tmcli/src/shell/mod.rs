@@ -5,7 +5,7 @@ use crate::{
     session::Session,
 };
 
-pub async fn run(session: Session) -> Result<(), ()> {
+pub async fn run(session: &mut Session) -> Result<(), ()> {
     let mut rl = Editor::<()>::new();
     loop {
         let prompt = match rl.readline("tmcli> ") {
@@ -38,7 +38,7 @@ pub async fn run(session: Session) -> Result<(), ()> {
                 continue;
             }
         };
-        if let Err(err) = send_command(cmd, &session).await {
+        if let Err(err) = send_command(cmd, session).await {
             eprintln!("{err}");
         }
     }
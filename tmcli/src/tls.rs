@@ -0,0 +1,68 @@
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, ServerName, pem::PemObject};
+use std::{path::Path, sync::Arc};
+use thiserror::Error;
+use tokio_rustls::{
+    TlsConnector,
+    rustls::{self, ClientConfig, RootCertStore},
+};
+
+#[derive(Error, Debug)]
+pub enum TlsError {
+    #[error("Failed to read `{path}`: {error}")]
+    Pem {
+        path: String,
+        error: rustls_pki_types::pem::Error,
+    },
+    #[error("{0}")]
+    Rustls(#[from] rustls::Error),
+    #[error("--cert and --key have to be given together")]
+    IncompleteClientCertificate,
+    #[error("Invalid server name `{0}`")]
+    InvalidServerName(String),
+}
+
+/// Builds a connector trusting the authorities of `ca_file`, presenting the certificate of
+/// `cert_file` and `key_file` to servers that ask for one.
+pub fn connector(
+    ca_file: &Path,
+    cert_file: Option<&Path>,
+    key_file: Option<&Path>,
+) -> Result<TlsConnector, TlsError> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(ca_file)? {
+        roots.add(cert)?;
+    }
+    let builder = ClientConfig::builder().with_root_certificates(roots);
+
+    let config = match (cert_file, key_file) {
+        (Some(cert_file), Some(key_file)) => {
+            let key = PrivateKeyDer::from_pem_file(key_file).map_err(|error| TlsError::Pem {
+                path: key_file.display().to_string(),
+                error,
+            })?;
+            builder.with_client_auth_cert(load_certs(cert_file)?, key)?
+        }
+        (None, None) => builder.with_no_client_auth(),
+        _ => return Err(TlsError::IncompleteClientCertificate),
+    };
+
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// Name the certificate of the server is checked against, the host part of `addr`.
+pub fn server_name(addr: &str) -> Result<ServerName<'static>, TlsError> {
+    let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+    ServerName::try_from(host.to_string())
+        .map_err(|_| TlsError::InvalidServerName(host.to_string()))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let pem_error = |error| TlsError::Pem {
+        path: path.display().to_string(),
+        error,
+    };
+    CertificateDer::pem_file_iter(path)
+        .map_err(pem_error)?
+        .collect::<Result<_, _>>()
+        .map_err(pem_error)
+}